@@ -0,0 +1,409 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use itertools::Itertools;
+
+use crate::error::{aoc_error, input_error, AocError, AocResult};
+
+/// One of the registers `w`, `x`, `y` and `z`, as an index.
+pub type Reg = usize;
+
+const NAMES: [char; 4] = ['w', 'x', 'y', 'z'];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+  Reg(Reg),
+  Imm(i64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instr {
+  Inp(Reg),
+  Add(Reg, Operand),
+  Mul(Reg, Operand),
+  Div(Reg, Operand),
+  Mod(Reg, Operand),
+  Eql(Reg, Operand),
+}
+
+impl fmt::Display for Operand {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Operand::Reg(r) => write!(f, "{}", NAMES[r]),
+      Operand::Imm(v) => write!(f, "{}", v),
+    }
+  }
+}
+
+/// The instruction as written in a program.
+impl fmt::Display for Instr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let (name, a, b) = match *self {
+      Instr::Inp(a) => return f.pad(&format!("inp {}", NAMES[a])),
+      Instr::Add(a, b) => ("add", a, b),
+      Instr::Mul(a, b) => ("mul", a, b),
+      Instr::Div(a, b) => ("div", a, b),
+      Instr::Mod(a, b) => ("mod", a, b),
+      Instr::Eql(a, b) => ("eql", a, b),
+    };
+    // Padded as a whole, so that traces line up.
+    f.pad(&format!("{} {} {}", name, NAMES[a], b))
+  }
+}
+
+fn parse_reg(input: &str, token: &str) -> AocResult<Reg> {
+  match token {
+    "w" => Ok(0),
+    "x" => Ok(1),
+    "y" => Ok(2),
+    "z" => Ok(3),
+    _ => Err(input_error(
+      input,
+      token,
+      "Expected a register w, x, y or z",
+    )),
+  }
+}
+
+fn parse_instr(input: &str, line: &str) -> AocResult<Instr> {
+  let operand = |token: &str| match token.parse() {
+    Ok(value) => Ok(Operand::Imm(value)),
+    Err(_) => parse_reg(input, token).map(Operand::Reg),
+  };
+  let tokens = line.split_whitespace().collect_vec();
+  match tokens[..] {
+    ["inp", a] => Ok(Instr::Inp(parse_reg(input, a)?)),
+    [op @ ("add" | "mul" | "div" | "mod" | "eql"), a, b] => {
+      let (a, b) = (parse_reg(input, a)?, operand(b)?);
+      Ok(match op {
+        "add" => Instr::Add(a, b),
+        "mul" => Instr::Mul(a, b),
+        "div" => Instr::Div(a, b),
+        "mod" => Instr::Mod(a, b),
+        _ => Instr::Eql(a, b),
+      })
+    }
+    ["inp" | "add" | "mul" | "div" | "mod" | "eql", ..] => {
+      Err(input_error(input, line, "Wrong number of operands"))
+    }
+    [op, ..] => Err(input_error(input, op, "Unknown instruction")),
+    [] => unreachable!("blank lines are skipped"),
+  }
+}
+
+/// Parse an ALU program, one instruction per line. Blank lines are skipped.
+pub fn parse_program(input: &str) -> AocResult<Vec<Instr>> {
+  input
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| parse_instr(input, line))
+    .collect()
+}
+
+/// The registers of the ALU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Machine {
+  pub regs: [i64; 4],
+}
+
+impl Machine {
+  pub fn new() -> Self { Machine::default() }
+
+  pub fn w(&self) -> i64 { self.regs[0] }
+  pub fn x(&self) -> i64 { self.regs[1] }
+  pub fn y(&self) -> i64 { self.regs[2] }
+  pub fn z(&self) -> i64 { self.regs[3] }
+
+  /// Execute a single instruction, reading from `inputs` for `inp`. Errors on
+  /// division by zero, modulo of negative numbers, overflow and missing
+  /// input, leaving the registers as they were.
+  pub fn step(&mut self, instr: &Instr, inputs: &mut impl Iterator<Item = i64>) -> AocResult<()> {
+    use Instr::*;
+    let regs = &mut self.regs;
+    let value = |b: Operand| match b {
+      Operand::Reg(r) => regs[r],
+      Operand::Imm(v) => v,
+    };
+    let overflow = || AocError::Custom(format!("Overflow in {}", instr));
+    match *instr {
+      Inp(a) => regs[a] = inputs.next().ok_or(aoc_error("Out of input"))?,
+      Add(a, b) => regs[a] = regs[a].checked_add(value(b)).ok_or_else(overflow)?,
+      Mul(a, b) => regs[a] = regs[a].checked_mul(value(b)).ok_or_else(overflow)?,
+      Div(a, b) => match value(b) {
+        0 => return Err(aoc_error("Division by zero")),
+        b => regs[a] = regs[a].checked_div(b).ok_or_else(overflow)?,
+      },
+      Mod(a, b) => match (regs[a], value(b)) {
+        (a, b) if a < 0 || b <= 0 => {
+          return Err(AocError::Custom(format!("Invalid modulo {} % {}", a, b)));
+        }
+        (_, b) => regs[a] = regs[a].checked_rem(b).ok_or_else(overflow)?,
+      },
+      Eql(a, b) => regs[a] = (regs[a] == value(b)) as i64,
+    }
+    Ok(())
+  }
+
+  /// Execute all of `program`.
+  pub fn run(
+    &mut self,
+    program: &[Instr],
+    inputs: &mut impl Iterator<Item = i64>,
+  ) -> AocResult<()> {
+    for (line, instr) in program.iter().enumerate() {
+      self.step(instr, inputs).map_err(|err| at_line(line, err))?;
+    }
+    Ok(())
+  }
+
+  /// `run`, writing the registers after every instruction to `out`.
+  pub fn trace(
+    &mut self,
+    program: &[Instr],
+    inputs: &mut impl Iterator<Item = i64>,
+    out: &mut impl Write,
+  ) -> AocResult<()> {
+    for (line, instr) in program.iter().enumerate() {
+      self.step(instr, inputs).map_err(|err| at_line(line, err))?;
+      writeln!(out, "{:>4}  {:<12} {}", line + 1, instr, self)?;
+    }
+    Ok(())
+  }
+}
+
+fn at_line(line: usize, err: AocError) -> AocError {
+  match err {
+    AocError::Custom(msg) => AocError::Custom(format!("line {}: {}", line + 1, msg)),
+    err => err,
+  }
+}
+
+/// The registers as `w=1 x=0 y=1 z=1`.
+impl fmt::Display for Machine {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let regs = NAMES
+      .iter()
+      .zip(self.regs)
+      .map(|(name, v)| format!("{}={}", name, v));
+    write!(f, "{}", regs.format(" "))
+  }
+}
+
+const DEBUG_HELP: &str = "\
+commands:
+  s, step [N]     execute the next N instructions (default 1)
+  c, continue     run until a breakpoint or the end
+  b, break LINE   set or clear a breakpoint before LINE
+  r, regs         show the registers
+  l, list         show the instructions around the current one
+  q, quit         stop debugging
+  h, help         show this message";
+
+/// Interactive debugger: run `program` on `inputs`, reading commands from
+/// `commands` and writing to `out` until the program ends or is quit.
+/// Returns the registers at that point.
+pub fn debug(
+  program: &[Instr],
+  inputs: &[i64],
+  commands: impl BufRead,
+  out: &mut impl Write,
+) -> AocResult<Machine> {
+  let mut machine = Machine::new();
+  let mut inputs = inputs.iter().copied();
+  let mut breakpoints = vec![false; program.len()];
+  let mut pc = 0;
+  let mut lines = commands.lines();
+
+  writeln!(out, "{} instructions, 'h' for help", program.len())?;
+  while pc < program.len() {
+    write!(out, "(alu) ")?;
+    out.flush()?;
+    let Some(line) = lines.next().transpose()? else {
+      break;
+    };
+    let words = line.split_whitespace().collect_vec();
+    let steps = match words[..] {
+      [] => continue,
+      ["s" | "step"] => 1,
+      ["s" | "step", n] => match n.parse() {
+        Ok(n) => n,
+        Err(_) => {
+          writeln!(out, "Invalid count '{}'", n)?;
+          continue;
+        }
+      },
+      ["c" | "continue"] => usize::MAX,
+      ["b" | "break", n] => {
+        match n.parse::<usize>() {
+          Ok(n @ 1..) if n <= program.len() => {
+            breakpoints[n - 1] = !breakpoints[n - 1];
+            let state = if breakpoints[n - 1] { "set" } else { "cleared" };
+            writeln!(out, "Breakpoint {} at line {}", state, n)?;
+          }
+          _ => writeln!(out, "No line '{}'", n)?,
+        }
+        continue;
+      }
+      ["r" | "regs"] => {
+        writeln!(out, "{}", machine)?;
+        continue;
+      }
+      ["l" | "list"] => {
+        for line in pc.saturating_sub(3)..(pc + 4).min(program.len()) {
+          let mark = if line == pc {
+            "=>"
+          } else if breakpoints[line] {
+            " *"
+          } else {
+            "  "
+          };
+          writeln!(out, "{} {:>4}  {}", mark, line + 1, program[line])?;
+        }
+        continue;
+      }
+      ["q" | "quit"] => break,
+      ["h" | "help"] => {
+        writeln!(out, "{}", DEBUG_HELP)?;
+        continue;
+      }
+      _ => {
+        writeln!(out, "Unknown command '{}', 'h' for help", line.trim())?;
+        continue;
+      }
+    };
+
+    for i in 0..steps {
+      if pc == program.len() || (i > 0 && breakpoints[pc]) {
+        break;
+      }
+      let instr = &program[pc];
+      if let Err(AocError::Custom(msg)) = machine.step(instr, &mut inputs) {
+        writeln!(out, "Error at line {}: {}", pc + 1, msg)?;
+        break;
+      }
+      pc += 1;
+      if steps != usize::MAX {
+        writeln!(out, "{:>4}  {:<12} {}", pc, instr, machine)?;
+      }
+    }
+    if pc < program.len() && breakpoints[pc] {
+      writeln!(out, "Breakpoint at line {}: {}", pc + 1, program[pc])?;
+    }
+  }
+  if pc == program.len() {
+    writeln!(out, "Program ended: {}", machine)?;
+  }
+  Ok(machine)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Binary digits of the input, from the puzzle text.
+  const BINARY: &str = "\
+inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2
+";
+
+  #[test]
+  fn run_and_display() {
+    let program = parse_program(BINARY).unwrap();
+    assert_eq!(program.iter().join("\n"), BINARY.trim_end());
+    let mut machine = Machine::new();
+    machine.run(&program, &mut [11].into_iter()).unwrap();
+    assert_eq!(
+      (machine.w(), machine.x(), machine.y(), machine.z()),
+      (1, 0, 1, 1)
+    );
+    assert_eq!(machine.to_string(), "w=1 x=0 y=1 z=1");
+    let err = Machine::new()
+      .run(&program, &mut std::iter::empty())
+      .unwrap_err();
+    assert!(err.to_string().contains("line 1: Out of input"), "{}", err);
+  }
+
+  #[test]
+  fn errors() {
+    let err = parse_program("inp w\nadd q 1\n").unwrap_err().to_string();
+    assert!(err.starts_with("line 2, column 5"), "{}", err);
+    assert!(parse_program("sub x 1").is_err());
+    assert!(parse_program("add x").is_err());
+    let mut machine = Machine { regs: [5, -1, 0, 0] };
+    let mut none = std::iter::empty();
+    assert!(machine
+      .step(&Instr::Div(0, Operand::Reg(2)), &mut none)
+      .is_err());
+    assert!(machine
+      .step(&Instr::Mod(1, Operand::Imm(2)), &mut none)
+      .is_err());
+    assert_eq!(machine.regs, [5, -1, 0, 0]);
+  }
+
+  #[test]
+  fn overflow() {
+    // 99 to the power 16, after the fourth squaring, does not fit.
+    let squares = parse_program(&format!("inp z\n{}", "mul z z\n".repeat(5))).unwrap();
+    let err = Machine::new()
+      .run(&squares, &mut [99].into_iter())
+      .unwrap_err();
+    assert!(
+      err.to_string().contains("line 5: Overflow in mul z z"),
+      "{}",
+      err
+    );
+
+    let mut machine = Machine { regs: [i64::MIN, -1, i64::MAX, 0] };
+    let mut none = std::iter::empty();
+    let err = machine
+      .step(&Instr::Div(0, Operand::Reg(1)), &mut none)
+      .unwrap_err();
+    assert!(err.to_string().contains("Overflow in div w x"), "{}", err);
+    assert!(machine
+      .step(&Instr::Add(2, Operand::Imm(1)), &mut none)
+      .is_err());
+    assert!(machine
+      .step(&Instr::Mul(0, Operand::Reg(1)), &mut none)
+      .is_err());
+    assert_eq!(machine.regs, [i64::MIN, -1, i64::MAX, 0]);
+  }
+
+  #[test]
+  fn trace_and_debugger() {
+    let program = parse_program(BINARY).unwrap();
+    let mut out = vec![];
+    Machine::new()
+      .trace(&program, &mut [6].into_iter(), &mut out)
+      .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.lines().count(), program.len());
+    assert_eq!(
+      out.lines().next().unwrap(),
+      "   1  inp w        w=6 x=0 y=0 z=0"
+    );
+
+    let commands = "b 4\nc\nr\nstep 2\nbogus\nc\n";
+    let mut out = vec![];
+    let machine = debug(&program, &[6], commands.as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Breakpoint at line 4: div w 2"), "{}", out);
+    assert!(out.contains("w=6 x=0 y=0 z=0\n"), "{}", out);
+    assert!(
+      out.contains("   5  add y w      w=3 x=0 y=3 z=0"),
+      "{}",
+      out
+    );
+    assert!(out.contains("Unknown command 'bogus'"), "{}", out);
+    assert!(out.ends_with("Program ended: w=0 x=1 y=1 z=0\n"), "{}", out);
+    assert_eq!(machine.regs, [0, 1, 1, 0]);
+  }
+}
@@ -0,0 +1,278 @@
+//! Known puzzle answers, read from a small subset of TOML:
+//!
+//! ```toml
+//! # Comments are allowed.
+//! [day01]
+//! part1 = 1564
+//! part2 = "1611"
+//!
+//! [day13]
+//! part2 = """
+//! ...
+//! """
+//! ```
+//!
+//! Values are integers, basic `"strings"` with escapes, literal `'strings'`
+//! or multi-line `"""strings"""` (where a newline right after the opening
+//! quotes is dropped, as in TOML).
+
+use std::collections::HashMap;
+
+use crate::cli::Part;
+use crate::error::{AocError, AocResult};
+
+pub const DEFAULT_PATH: &str = "answers.toml";
+
+#[derive(Debug, Default)]
+pub struct Answers {
+  answers: HashMap<(u32, Part), String>,
+}
+
+fn error(line: usize, msg: &str) -> AocError {
+  AocError::ParserError(format!("answers line {}: {}", line + 1, msg))
+}
+
+/// Parse a `[dayNN]` table header.
+fn parse_header(line: usize, s: &str) -> AocResult<u32> {
+  let name = s
+    .strip_prefix('[')
+    .and_then(|s| s.strip_suffix(']'))
+    .ok_or_else(|| error(line, "Unterminated table header"))?
+    .trim();
+  match name.trim_start_matches("day").parse() {
+    Ok(day) if (1..=25).contains(&day) => Ok(day),
+    _ => Err(error(line, &format!("Invalid day '{}'", name))),
+  }
+}
+
+fn parse_key(line: usize, s: &str) -> AocResult<Part> {
+  match s.trim() {
+    "part1" => Ok(Part::One),
+    "part2" => Ok(Part::Two),
+    key => Err(error(
+      line,
+      &format!("Unknown key '{}', expected part1 or part2", key),
+    )),
+  }
+}
+
+fn unescape(line: usize, s: &str) -> AocResult<String> {
+  let mut res = String::new();
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some('n') => res.push('\n'),
+        Some('t') => res.push('\t'),
+        Some('r') => res.push('\r'),
+        Some('"') => res.push('"'),
+        Some('\\') => res.push('\\'),
+        other => return Err(error(line, &format!("Invalid escape {:?}", other))),
+      },
+      c => res.push(c),
+    }
+  }
+  Ok(res)
+}
+
+/// Parse the rest of a basic string up to the closing quote, returning the
+/// unescaped string and the remaining text.
+fn parse_basic(line: usize, s: &str) -> AocResult<(String, &str)> {
+  let mut escaped = false;
+  for (i, c) in s.char_indices() {
+    match c {
+      '"' if !escaped => return Ok((unescape(line, &s[..i])?, &s[i + 1..])),
+      '\\' => escaped = !escaped,
+      _ => escaped = false,
+    }
+  }
+  Err(error(line, "Unterminated string"))
+}
+
+/// Anything after a value must be a comment.
+fn expect_end(line: usize, rest: &str) -> AocResult<()> {
+  let rest = rest.trim();
+  if rest.is_empty() || rest.starts_with('#') {
+    Ok(())
+  } else {
+    Err(error(line, &format!("Unexpected '{}' after value", rest)))
+  }
+}
+
+impl Answers {
+  pub fn parse(text: &str) -> AocResult<Answers> {
+    let mut answers = HashMap::new();
+    let mut day = None;
+    let mut lines = text.lines().enumerate();
+
+    while let Some((lineno, line)) = lines.next() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      if line.starts_with('[') {
+        let end = line.find(']').map_or(line.len(), |i| i + 1);
+        day = Some(parse_header(lineno, &line[..end])?);
+        expect_end(lineno, &line[end..])?;
+        continue;
+      }
+
+      let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| error(lineno, "Expected 'key = value'"))?;
+      let day = day.ok_or_else(|| error(lineno, "Answer outside of a [dayNN] table"))?;
+      let part = parse_key(lineno, key)?;
+      let value = value.trim();
+
+      let answer = if let Some(rest) = value.strip_prefix("\"\"\"") {
+        // Multi-line string, collect lines until the closing quotes.
+        let mut text = rest.to_owned();
+        while !text.contains("\"\"\"") {
+          let (_, next) = lines
+            .next()
+            .ok_or_else(|| error(lineno, "Unterminated multi-line string"))?;
+          text.push('\n');
+          text += next;
+        }
+        let text = text.strip_prefix('\n').unwrap_or(&text);
+        let (body, rest) = text.split_once("\"\"\"").unwrap();
+        expect_end(lineno, rest)?;
+        unescape(lineno, body)?
+      } else if let Some(rest) = value.strip_prefix('"') {
+        let (answer, rest) = parse_basic(lineno, rest)?;
+        expect_end(lineno, rest)?;
+        answer
+      } else if let Some(rest) = value.strip_prefix('\'') {
+        let (answer, rest) = rest
+          .split_once('\'')
+          .ok_or_else(|| error(lineno, "Unterminated string"))?;
+        expect_end(lineno, rest)?;
+        answer.to_owned()
+      } else {
+        let answer = value[..value.find('#').unwrap_or(value.len())].trim();
+        if answer.parse::<i64>().is_err() {
+          return Err(error(lineno, &format!("Invalid value '{}'", answer)));
+        }
+        answer.to_owned()
+      };
+
+      if answers.insert((day, part), answer).is_some() {
+        return Err(error(
+          lineno,
+          &format!("Duplicate answer for day {} part {}", day, part),
+        ));
+      }
+    }
+    Ok(Answers { answers })
+  }
+
+  pub fn load(path: &str) -> AocResult<Answers> {
+    let text = std::fs::read_to_string(path)
+      .map_err(|err| AocError::Custom(format!("Could not read '{}': {}", path, err)))?;
+    Answers::parse(&text)
+  }
+
+  pub fn get(&self, day: u32, part: Part) -> Option<&str> {
+    self.answers.get(&(day, part)).map(|s| s.as_str())
+  }
+}
+
+/// Whether `answer` matches `expected`, ignoring trailing whitespace on
+/// every line and surrounding blank lines, which matters for answers drawn
+/// as ASCII art.
+pub fn matches(expected: &str, answer: &str) -> bool {
+  fn normalize(s: &str) -> Vec<&str> {
+    let lines = s.lines().map(|l| l.trim_end()).collect::<Vec<_>>();
+    let start = lines
+      .iter()
+      .position(|l| !l.is_empty())
+      .unwrap_or(lines.len());
+    let end = lines
+      .iter()
+      .rposition(|l| !l.is_empty())
+      .map_or(start, |i| i + 1);
+    lines[start..end].to_vec()
+  }
+  normalize(expected) == normalize(answer)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn values() {
+    let text = r#"
+# Known answers.
+[day01]
+part1 = 1564   # an integer
+part2 = "16\"1\\1\tx\n"
+
+  [ day13 ]  # spaces are fine
+part1 = 'C:\raw'
+part2 = """
+#..#
+#..#"""
+
+[day25]
+part1 = """ one line """
+part2 = -7
+"#;
+    let answers = Answers::parse(text).unwrap();
+    assert_eq!(answers.get(1, Part::One), Some("1564"));
+    assert_eq!(answers.get(1, Part::Two), Some("16\"1\\1\tx\n"));
+    assert_eq!(answers.get(13, Part::One), Some("C:\\raw"));
+    assert_eq!(answers.get(13, Part::Two), Some("#..#\n#..#"));
+    assert_eq!(answers.get(25, Part::One), Some(" one line "));
+    assert_eq!(answers.get(25, Part::Two), Some("-7"));
+    assert_eq!(answers.get(2, Part::One), None);
+    assert_eq!(Answers::parse("").unwrap().get(1, Part::One), None);
+  }
+
+  #[test]
+  fn errors() {
+    let err = |text: &str| Answers::parse(text).unwrap_err().to_string();
+    for (text, msg) in [
+      ("[day01", "line 1: Unterminated table header"),
+      ("[day26]", "line 1: Invalid day 'day26'"),
+      ("[dayx]", "Invalid day 'dayx'"),
+      ("[day01] part1 = 1", "Unexpected 'part1 = 1' after value"),
+      (
+        "[day01]\npart3 = 1",
+        "line 2: Unknown key 'part3', expected part1 or part2",
+      ),
+      ("part1 = 1", "line 1: Answer outside of a [dayNN] table"),
+      ("[day01]\npart1 1", "line 2: Expected 'key = value'"),
+      ("[day01]\npart1 = \"a\\q\"", "Invalid escape Some('q')"),
+      ("[day01]\npart1 = \"\"\"a\\\"\"\"", "Invalid escape None"),
+      ("[day01]\npart1 = \"abc", "line 2: Unterminated string"),
+      ("[day01]\npart1 = 'abc", "line 2: Unterminated string"),
+      ("[day01]\npart1 = \"a\" b", "Unexpected 'b' after value"),
+      ("[day01]\npart1 = 'a' b", "Unexpected 'b' after value"),
+      (
+        "[day01]\npart1 = \"\"\"a\nb",
+        "line 2: Unterminated multi-line string",
+      ),
+      (
+        "[day01]\npart1 = \"\"\"a\n\"\"\" b",
+        "Unexpected 'b' after value",
+      ),
+      ("[day01]\npart1 = 12x", "line 2: Invalid value '12x'"),
+      (
+        "[day01]\npart1 = 1\npart1 = 2",
+        "line 3: Duplicate answer for day 1",
+      ),
+    ] {
+      let err = err(text);
+      assert!(err.contains(msg), "{:?}: {}", text, err);
+    }
+  }
+
+  #[test]
+  fn matching() {
+    assert!(matches("1564", "1564"));
+    assert!(!matches("1564", "1565"));
+    assert!(matches("\n#..#  \n#..#\n\n", "#..#\n#..#   "));
+    assert!(!matches("#..#\n\n#..#", "#..#\n#..#"));
+  }
+}
@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::bench::{DayBench, Stats};
+use crate::error::{aoc_error, AocResult};
+
+/// Directory baselines are stored in, relative to the working directory like `inputs/`.
+pub const BASELINE_DIR: &str = "baselines";
+
+const HEADER: &str = "day,n,min_ns,median_ns,mean_ns,stddev_ns,p95_ns";
+
+/// The file of baseline `name`, which has to stay inside `BASELINE_DIR`.
+pub fn path(name: &str) -> AocResult<PathBuf> {
+  if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+    return Err(aoc_error(&format!(
+      "Invalid baseline name '{}', it can not be empty or contain '/', '\\' or '..'",
+      name
+    )));
+  }
+  Ok(PathBuf::from(BASELINE_DIR).join(format!("{}.csv", name)))
+}
+
+/// Store the total time statistics of every day under `name`.
+pub fn save(name: &str, results: &[DayBench]) -> AocResult<()> {
+  let mut lines = vec![HEADER.to_owned()];
+  for r in results {
+    let s = &r.stats;
+    lines.push(format!(
+      "{},{},{},{},{},{},{}",
+      r.day,
+      s.n,
+      s.min.as_nanos(),
+      s.median.as_nanos(),
+      s.mean.as_nanos(),
+      s.stddev.as_nanos(),
+      s.p95.as_nanos()
+    ));
+  }
+  let path = path(name)?;
+  std::fs::create_dir_all(BASELINE_DIR)?;
+  std::fs::write(path, lines.join("\n") + "\n")?;
+  Ok(())
+}
+
+pub fn load(name: &str) -> AocResult<BTreeMap<u32, Stats>> {
+  let path = path(name)?;
+  let text = std::fs::read_to_string(&path).map_err(|err| {
+    aoc_error(&format!(
+      "Could not read baseline '{}' ({}): {}",
+      name,
+      path.display(),
+      err
+    ))
+  })?;
+
+  let mut lines = text.lines();
+  if lines.next() != Some(HEADER) {
+    return Err(aoc_error(&format!(
+      "{} is not a baseline file",
+      path.display()
+    )));
+  }
+  lines
+    .map(|line| {
+      let fields = line
+        .split(',')
+        .map(|x| x.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()?;
+      let [day, n, min, median, mean, stddev, p95] = fields[..] else {
+        return Err(aoc_error(&format!("Malformed baseline line '{}'", line)));
+      };
+      let ns = Duration::from_nanos;
+      let stats = Stats {
+        n: n as usize,
+        min: ns(min),
+        median: ns(median),
+        mean: ns(mean),
+        stddev: ns(stddev),
+        p95: ns(p95),
+      };
+      Ok((day as u32, stats))
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+  Regression,
+  Improvement,
+  Unchanged,
+}
+
+pub struct Comparison {
+  pub day: u32,
+  pub baseline: Stats,
+  pub current: Stats,
+  /// Relative change of the mean, positive when slower.
+  pub change: f64,
+  pub verdict: Verdict,
+}
+
+/// Two sided 95% critical value of Student's t distribution, using the
+/// Cornish-Fisher expansion around the normal distribution.
+fn t_critical(df: f64) -> f64 {
+  let z: f64 = 1.96;
+  z + (z.powi(3) + z) / (4.0 * df)
+    + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df * df)
+}
+
+/// Welch's t-test for a difference in means.
+fn significant(a: &Stats, b: &Stats) -> bool {
+  if a.n < 2 || b.n < 2 {
+    return false;
+  }
+  let var = |s: &Stats| s.stddev.as_secs_f64().powi(2) / s.n as f64;
+  let (va, vb) = (var(a), var(b));
+  let diff = (b.mean.as_secs_f64() - a.mean.as_secs_f64()).abs();
+  if va + vb == 0.0 {
+    return diff > 0.0;
+  }
+  let df = (va + vb).powi(2) / (va.powi(2) / (a.n - 1) as f64 + vb.powi(2) / (b.n - 1) as f64);
+  diff / (va + vb).sqrt() > t_critical(df)
+}
+
+/// Compare the days present in both the baseline and the current results.
+/// A change only counts if it is significant and larger than `threshold`.
+pub fn compare(
+  baseline: &BTreeMap<u32, Stats>,
+  results: &[DayBench],
+  threshold: f64,
+) -> Vec<Comparison> {
+  results
+    .iter()
+    .filter_map(|r| {
+      let base = *baseline.get(&r.day)?;
+      let change = r.stats.mean.as_secs_f64() / base.mean.as_secs_f64() - 1.0;
+      let verdict = if !significant(&base, &r.stats) || change.abs() <= threshold {
+        Verdict::Unchanged
+      } else if change > 0.0 {
+        Verdict::Regression
+      } else {
+        Verdict::Improvement
+      };
+      Some(Comparison { day: r.day, baseline: base, current: r.stats, change, verdict })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn stats(n: usize, mean_ms: u64, stddev_ms: u64) -> Stats {
+    let ms = Duration::from_millis;
+    Stats {
+      n,
+      min: ms(mean_ms),
+      median: ms(mean_ms),
+      mean: ms(mean_ms),
+      stddev: ms(stddev_ms),
+      p95: ms(mean_ms),
+    }
+  }
+
+  fn bench(day: u32, stats: Stats) -> DayBench {
+    DayBench {
+      day,
+      title: "",
+      part1: String::new(),
+      part2: String::new(),
+      samples: vec![],
+      stats,
+    }
+  }
+
+  #[test]
+  fn baseline_names() {
+    assert_eq!(path("main").unwrap(), PathBuf::from("baselines/main.csv"));
+    assert!(path("v1.2").is_ok());
+    for name in ["", "../x", "a/b", "a\\b", "..", "x..y"] {
+      let err = path(name).unwrap_err().to_string();
+      assert!(err.contains("Invalid baseline name"), "{:?}: {}", name, err);
+    }
+  }
+
+  #[test]
+  fn critical_values() {
+    // Tabulated two sided 95% values.
+    for (df, t) in [(10.0, 2.228), (30.0, 2.042), (1e9, 1.960)] {
+      assert!((t_critical(df) - t).abs() < 0.005, "df {}", df);
+    }
+  }
+
+  #[test]
+  fn welch_t_test() {
+    // t = 10 / sqrt(2 * 1 / 10) = 22.4
+    assert!(significant(&stats(10, 100, 1), &stats(10, 110, 1)));
+    assert!(significant(&stats(10, 110, 1), &stats(10, 100, 1)));
+    // t = 10 / sqrt(2 * 400 / 10) = 1.1
+    assert!(!significant(&stats(10, 100, 20), &stats(10, 110, 20)));
+    // t = 20 / sqrt(400 / 10 + 400 / 20) = 2.58, df = 21.3
+    assert!(significant(&stats(10, 100, 20), &stats(20, 120, 20)));
+    // Too few samples for a variance.
+    assert!(!significant(&stats(1, 100, 0), &stats(10, 200, 1)));
+    // Without any noise every difference counts.
+    assert!(significant(&stats(5, 100, 0), &stats(5, 101, 0)));
+    assert!(!significant(&stats(5, 100, 0), &stats(5, 100, 0)));
+  }
+
+  #[test]
+  fn threshold_and_verdicts() {
+    let baseline = BTreeMap::from([(1, stats(10, 1000, 1)), (2, stats(10, 1000, 100))]);
+    let verdict = |day, mean_ms, threshold| {
+      let results = [bench(day, stats(10, mean_ms, 1))];
+      let comparisons = compare(&baseline, &results, threshold);
+      assert_eq!(comparisons.len(), 1);
+      comparisons[0].verdict
+    };
+    assert_eq!(verdict(1, 1250, 0.24), Verdict::Regression);
+    // A change of exactly the threshold does not count.
+    assert_eq!(verdict(1, 1250, 0.25), Verdict::Unchanged);
+    assert_eq!(verdict(1, 750, 0.24), Verdict::Improvement);
+    assert_eq!(verdict(1, 750, 0.25), Verdict::Unchanged);
+    // Larger than the threshold, but within the noise of the baseline.
+    assert_eq!(verdict(2, 1050, 0.01), Verdict::Unchanged);
+
+    let results = [bench(1, stats(10, 1250, 1)), bench(3, stats(10, 1, 0))];
+    let comparisons = compare(&baseline, &results, 0.05);
+    assert_eq!(comparisons.len(), 1);
+    assert_eq!(comparisons[0].day, 1);
+    assert!((comparisons[0].change - 0.25).abs() < 1e-12);
+  }
+}
@@ -0,0 +1,216 @@
+use std::time::{Duration, Instant};
+
+use crate::cli::{self, Part};
+use crate::error::AocResult;
+use crate::parallel;
+use crate::solutions::Solution;
+
+/// Samples are never collected beyond these limits in adaptive mode.
+const ADAPTIVE_MAX_SAMPLES: usize = 10_000;
+const ADAPTIVE_MAX_TIME: Duration = Duration::from_secs(10);
+/// How often the confidence interval is recomputed in adaptive mode.
+const ADAPTIVE_CHECK_EVERY: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchOptions {
+  /// Number of measured runs, or the minimum number in adaptive mode.
+  pub iterations: usize,
+  /// Unmeasured runs done before sampling starts.
+  pub warmup: usize,
+  /// Fraction of samples dropped from each end before computing statistics.
+  pub trim: f64,
+  /// Keep sampling until the 95% confidence interval of the mean is within
+  /// this fraction of the mean.
+  pub adaptive: Option<f64>,
+}
+
+impl Default for BenchOptions {
+  fn default() -> Self { BenchOptions { iterations: 10, warmup: 1, trim: 0.0, adaptive: None } }
+}
+
+/// Wall-clock time of each phase, plus the CPU time of the whole run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timing {
+  pub parse: Duration,
+  pub part1: Duration,
+  pub part2: Duration,
+  /// CPU time the process used during the run, over all threads. Only
+  /// attributable to this run if nothing else runs at the same time, and
+  /// zero where CPU time is not available.
+  pub cpu: Duration,
+}
+
+impl Timing {
+  pub fn solve(&self) -> Duration { self.part1 + self.part2 }
+  pub fn total(&self) -> Duration { self.parse + self.solve() }
+}
+
+/// Timing and answers of a single run.
+pub struct Run {
+  pub timing: Timing,
+  pub part1: Option<String>,
+  pub part2: Option<String>,
+}
+
+/// Parse and solve `input` once, skipping the part not asked for.
+pub fn run(solution: &dyn Solution, input: &str, part: Option<Part>) -> AocResult<Run> {
+  let mut timing = Timing::default();
+  let (mut part1, mut part2) = (None, None);
+  let cpu = parallel::cpu_time();
+
+  let t = Instant::now();
+  let parsed = solution.parse(input)?;
+  timing.parse = t.elapsed();
+
+  if part != Some(Part::Two) {
+    let t = Instant::now();
+    part1 = Some(solution.part1(&parsed)?);
+    timing.part1 = t.elapsed();
+  }
+  if part != Some(Part::One) {
+    let t = Instant::now();
+    part2 = Some(solution.part2(&parsed)?);
+    timing.part2 = t.elapsed();
+  }
+  if let (Some(start), Some(end)) = (cpu, parallel::cpu_time()) {
+    timing.cpu = end.saturating_sub(start);
+  }
+  Ok(Run { timing, part1, part2 })
+}
+
+/// Answers of several days, solved on a pool of threads.
+pub struct RunAll {
+  pub jobs: usize,
+  /// Day, title and outcome of every run, in the order of the solutions.
+  pub runs: Vec<(u32, &'static str, AocResult<Run>)>,
+  /// Wall-clock and process CPU time of the whole batch.
+  pub wall: Duration,
+  pub cpu: Duration,
+}
+
+/// Solve every day in `solutions` on its default input, `jobs` days at a time.
+/// A failing day does not stop the others.
+pub fn run_all(solutions: &[&'static dyn Solution], jobs: usize) -> RunAll {
+  let start = Instant::now();
+  let cpu = parallel::cpu_time();
+  let runs = parallel::map(solutions, jobs, |&solution| {
+    let res = cli::read_input(&cli::default_input(solution.day()))
+      .and_then(|input| run(solution, &input, None));
+    (solution.day(), solution.title(), res)
+  });
+  let wall = start.elapsed();
+  let cpu = match (cpu, parallel::cpu_time()) {
+    (Some(start), Some(end)) => end.saturating_sub(start),
+    _ => Duration::ZERO,
+  };
+  RunAll { jobs, runs, wall, cpu }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+  /// Number of samples left after trimming.
+  pub n: usize,
+  pub min: Duration,
+  pub median: Duration,
+  pub mean: Duration,
+  pub stddev: Duration,
+  pub p95: Duration,
+}
+
+impl Stats {
+  /// Statistics of `samples` after dropping the `trim` fraction of samples
+  /// from both the low and the high end.
+  pub fn new(samples: &[Duration], trim: f64) -> Stats {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let cut = (sorted.len() as f64 * trim).floor() as usize;
+    let kept = match sorted.get(cut..sorted.len() - cut) {
+      Some(kept) if !kept.is_empty() => kept,
+      _ => return Stats::default(),
+    };
+
+    let n = kept.len();
+    let secs = kept.iter().map(|d| d.as_secs_f64()).collect::<Vec<_>>();
+    let mean = secs.iter().sum::<f64>() / n as f64;
+    let variance = if n > 1 {
+      secs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+    } else {
+      0.0
+    };
+    let median = if n % 2 == 1 { kept[n / 2] } else { (kept[n / 2 - 1] + kept[n / 2]) / 2 };
+    // Nearest rank.
+    let p95 = kept[((0.95 * n as f64).ceil() as usize).clamp(1, n) - 1];
+
+    Stats {
+      n,
+      min: kept[0],
+      median,
+      mean: Duration::from_secs_f64(mean),
+      stddev: Duration::from_secs_f64(variance.sqrt()),
+      p95,
+    }
+  }
+
+  /// Half-width of the 95% confidence interval of the mean, relative to the mean.
+  pub fn relative_ci(&self) -> f64 {
+    if self.n < 2 || self.mean.is_zero() {
+      return f64::INFINITY;
+    }
+    1.96 * self.stddev.as_secs_f64() / (self.n as f64).sqrt() / self.mean.as_secs_f64()
+  }
+}
+
+/// All measurements of one day.
+pub struct DayBench {
+  pub day: u32,
+  pub title: &'static str,
+  pub part1: String,
+  pub part2: String,
+  pub samples: Vec<Timing>,
+  /// Statistics over the total (parse + solve) time.
+  pub stats: Stats,
+}
+
+impl DayBench {
+  /// Statistics of one phase, e.g. `bench.stats_of(|t| t.parse)`.
+  pub fn stats_of(&self, f: impl Fn(&Timing) -> Duration, trim: f64) -> Stats {
+    Stats::new(&self.samples.iter().map(f).collect::<Vec<_>>(), trim)
+  }
+}
+
+pub fn benchmark(solution: &dyn Solution, input: &str, opts: &BenchOptions) -> AocResult<DayBench> {
+  for _ in 0..opts.warmup {
+    run(solution, input, None)?;
+  }
+
+  let first = run(solution, input, None)?;
+  let mut samples = vec![first.timing];
+  let totals = |samples: &[Timing]| samples.iter().map(|t| t.total()).collect::<Vec<_>>();
+
+  let start = Instant::now();
+  loop {
+    let done = match opts.adaptive {
+      None => samples.len() >= opts.iterations,
+      Some(target) => {
+        samples.len() >= ADAPTIVE_MAX_SAMPLES
+          || start.elapsed() >= ADAPTIVE_MAX_TIME
+          || (samples.len() >= opts.iterations.max(2)
+            && samples.len() % ADAPTIVE_CHECK_EVERY == 0
+            && Stats::new(&totals(&samples), opts.trim).relative_ci() <= target)
+      }
+    };
+    if done {
+      break;
+    }
+    samples.push(run(solution, input, None)?.timing);
+  }
+
+  Ok(DayBench {
+    day: solution.day(),
+    title: solution.title(),
+    part1: first.part1.unwrap_or_default(),
+    part2: first.part2.unwrap_or_default(),
+    stats: Stats::new(&totals(&samples), opts.trim),
+    samples,
+  })
+}
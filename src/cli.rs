@@ -0,0 +1,337 @@
+use crate::answers;
+use crate::bench::BenchOptions;
+use crate::error::{AocError, AocResult};
+use crate::report::Format;
+
+pub const USAGE: &str = "\
+usage: aoc <command> [options]
+
+commands:
+  run <day> [--input PATH] [--part 1|2]  solve a single day (PATH - is stdin)
+      [--trace]                          and show how, for days that can
+  run-all [days...] [--jobs N]           solve all days, or only the given ones,
+                                         N at a time (default 1)
+  benchmark [days...] [options]          time all days, or only the given ones
+  verify [days...] [--answers PATH]      check answers against answers.toml
+  alu <program> [--input N,...]          run an ALU program (day 24) on the
+      [--digits D] [--trace|--debug]     inputs, or on the digits of D
+  list                                   list the available days
+  help                                   show this message
+
+alu prints the registers when the program ends. --trace prints them after
+every instruction, --debug steps through the program interactively.
+
+run, run-all and benchmark take --format table|json|csv (default table), and
+--parallel to let expensive days use all cores internally.
+
+benchmark options:
+  --iterations N    number of measured runs per day (default 10)
+  --warmup M        unmeasured runs before measuring (default 1)
+  --trim P%         drop the fastest and slowest P% of runs (default 0%)
+  --adaptive P%     sample until the 95% confidence interval of the mean is
+                    within P% of the mean, using at least N runs
+  --save-baseline NAME  store the timings as baselines/NAME.csv
+  --compare NAME        compare with a saved baseline, fail on regressions
+  --threshold P%        ignore changes of the mean below P% (default 5%)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Part {
+  One,
+  Two,
+}
+
+impl std::fmt::Display for Part {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Part::One => write!(f, "1"),
+      Part::Two => write!(f, "2"),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+  Run {
+    day: u32,
+    input: Option<String>,
+    part: Option<Part>,
+    format: Format,
+    parallel: bool,
+    /// Also print the day's trace, see `Day::trace`.
+    trace: bool,
+  },
+  RunAll {
+    days: Vec<u32>,
+    /// Number of days solved at the same time.
+    jobs: usize,
+    format: Format,
+    parallel: bool,
+  },
+  Benchmark {
+    days: Vec<u32>,
+    options: BenchOptions,
+    format: Format,
+    parallel: bool,
+    save_baseline: Option<String>,
+    compare: Option<String>,
+    /// Relative slowdown below which a significant change is not a regression.
+    threshold: f64,
+  },
+  Verify {
+    days: Vec<u32>,
+    answers: String,
+  },
+  Alu {
+    program: String,
+    inputs: Vec<i64>,
+    trace: bool,
+    /// Step through the program with `alu::debug`.
+    debug: bool,
+  },
+  List,
+  Help,
+}
+
+pub fn usage_error(msg: &str) -> AocError { AocError::Usage(msg.to_owned()) }
+
+/// Read puzzle input from `path`, or from stdin if `path` is `-`.
+pub fn read_input(path: &str) -> AocResult<String> {
+  let res = if path == "-" {
+    std::io::read_to_string(std::io::stdin())
+  } else {
+    std::fs::read_to_string(path)
+  };
+  res.map_err(|err| AocError::Custom(format!("Could not read '{}': {}", path, err)))
+}
+
+/// Default input location for a day, e.g. `inputs/07input`.
+pub fn default_input(day: u32) -> String { format!("inputs/{:02}input", day) }
+
+fn parse_day(s: &str) -> AocResult<u32> {
+  let day = s
+    .trim_start_matches("day")
+    .parse::<u32>()
+    .map_err(|_| usage_error(&format!("Invalid day '{}'", s)))?;
+  if !(1..=25).contains(&day) {
+    return Err(usage_error(&format!("Day {} is not in 1..=25", day)));
+  }
+  Ok(day)
+}
+
+fn parse_part(s: &str) -> AocResult<Part> {
+  match s {
+    "1" => Ok(Part::One),
+    "2" => Ok(Part::Two),
+    _ => Err(usage_error(&format!(
+      "Invalid part '{}', expected 1 or 2",
+      s
+    ))),
+  }
+}
+
+fn parse_count(s: &str) -> AocResult<usize> {
+  s.parse()
+    .map_err(|_| usage_error(&format!("Invalid count '{}'", s)))
+}
+
+/// A percentage like `10%` (or just `10`) as a fraction.
+fn parse_percent(s: &str) -> AocResult<f64> {
+  match s.trim_end_matches('%').parse::<f64>() {
+    Ok(p) if (0.0..100.0).contains(&p) => Ok(p / 100.0),
+    _ => Err(usage_error(&format!("Invalid percentage '{}'", s))),
+  }
+}
+
+fn parse_benchmark(args: &[String]) -> AocResult<Command> {
+  let mut days = vec![];
+  let mut options = BenchOptions::default();
+  let mut format = Format::default();
+  let mut save_baseline = None;
+  let mut compare = None;
+  let mut threshold = 0.05;
+  let mut parallel = false;
+
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    let mut value = |flag: &str| {
+      it.next()
+        .ok_or_else(|| usage_error(&format!("Missing value for {}", flag)))
+    };
+    match arg.as_str() {
+      "--save-baseline" => save_baseline = Some(value(arg)?.clone()),
+      "--compare" => compare = Some(value(arg)?.clone()),
+      "--threshold" => threshold = parse_percent(value(arg)?)?,
+      "--iterations" | "-n" => options.iterations = parse_count(value(arg)?)?,
+      "--warmup" => options.warmup = parse_count(value(arg)?)?,
+      "--trim" => options.trim = parse_percent(value(arg)?)?,
+      "--adaptive" => options.adaptive = Some(parse_percent(value(arg)?)?),
+      "--format" | "-f" => format = value(arg)?.parse()?,
+      "--parallel" => parallel = true,
+      flag if flag.starts_with('-') => {
+        return Err(usage_error(&format!("Unknown option '{}'", flag)))
+      }
+      _ => days.push(parse_day(arg)?),
+    }
+  }
+
+  if options.iterations == 0 {
+    return Err(usage_error("--iterations must be at least 1"));
+  }
+  if options.trim >= 0.5 {
+    return Err(usage_error("--trim must be below 50%"));
+  }
+  Ok(Command::Benchmark { days, options, format, parallel, save_baseline, compare, threshold })
+}
+
+fn parse_run_all(args: &[String]) -> AocResult<Command> {
+  let mut days = vec![];
+  let mut jobs = 1;
+  let mut format = Format::default();
+  let mut parallel = false;
+
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    let mut value = |flag: &str| {
+      it.next()
+        .ok_or_else(|| usage_error(&format!("Missing value for {}", flag)))
+    };
+    match arg.as_str() {
+      "--jobs" | "-j" => jobs = parse_count(value(arg)?)?,
+      "--format" | "-f" => format = value(arg)?.parse()?,
+      "--parallel" => parallel = true,
+      flag if flag.starts_with('-') => {
+        return Err(usage_error(&format!("Unknown option '{}'", flag)))
+      }
+      _ => days.push(parse_day(arg)?),
+    }
+  }
+
+  if jobs == 0 {
+    return Err(usage_error("--jobs must be at least 1"));
+  }
+  Ok(Command::RunAll { days, jobs, format, parallel })
+}
+
+fn parse_verify(args: &[String]) -> AocResult<Command> {
+  let mut days = vec![];
+  let mut answers = answers::DEFAULT_PATH.to_owned();
+
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    match arg.as_str() {
+      "--answers" | "-a" => {
+        answers = it
+          .next()
+          .ok_or_else(|| usage_error(&format!("Missing value for {}", arg)))?
+          .clone()
+      }
+      flag if flag.starts_with('-') => {
+        return Err(usage_error(&format!("Unknown option '{}'", flag)))
+      }
+      _ => days.push(parse_day(arg)?),
+    }
+  }
+  Ok(Command::Verify { days, answers })
+}
+
+fn parse_run(args: &[String]) -> AocResult<Command> {
+  let mut day = None;
+  let mut input = None;
+  let mut part = None;
+  let mut format = Format::default();
+  let mut parallel = false;
+  let mut trace = false;
+
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    let mut value = |flag: &str| {
+      it.next()
+        .ok_or_else(|| usage_error(&format!("Missing value for {}", flag)))
+    };
+    match arg.as_str() {
+      "--input" | "-i" => input = Some(value(arg)?.clone()),
+      "--trace" | "-t" => trace = true,
+      "--part" | "-p" => part = Some(parse_part(value(arg)?)?),
+      "--format" | "-f" => format = value(arg)?.parse()?,
+      "--parallel" => parallel = true,
+      flag if flag.starts_with('-') => {
+        return Err(usage_error(&format!("Unknown option '{}'", flag)))
+      }
+      _ if day.is_none() => day = Some(parse_day(arg)?),
+      _ => return Err(usage_error(&format!("Unexpected argument '{}'", arg))),
+    }
+  }
+
+  let day = day.ok_or_else(|| usage_error("Missing day for run"))?;
+  Ok(Command::Run { day, input, part, format, parallel, trace })
+}
+
+fn parse_alu(args: &[String]) -> AocResult<Command> {
+  let mut program = None;
+  let mut inputs = vec![];
+  let mut trace = false;
+  let mut debug = false;
+
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    let mut value = |flag: &str| {
+      it.next()
+        .ok_or_else(|| usage_error(&format!("Missing value for {}", flag)))
+    };
+    match arg.as_str() {
+      "--input" | "-i" => {
+        for n in value(arg)?.split(',') {
+          let n = n.trim();
+          inputs.push(
+            n.parse()
+              .map_err(|_| usage_error(&format!("Invalid input '{}'", n)))?,
+          );
+        }
+      }
+      "--digits" => {
+        let digits = value(arg)?;
+        for c in digits.chars() {
+          let d = c
+            .to_digit(10)
+            .ok_or_else(|| usage_error(&format!("Invalid digits '{}'", digits)))?;
+          inputs.push(d as i64);
+        }
+      }
+      "--trace" | "-t" => trace = true,
+      "--debug" => debug = true,
+      flag if flag.starts_with('-') && flag != "-" => {
+        return Err(usage_error(&format!("Unknown option '{}'", flag)))
+      }
+      _ if program.is_none() => program = Some(arg.clone()),
+      _ => return Err(usage_error(&format!("Unexpected argument '{}'", arg))),
+    }
+  }
+
+  let program = program.ok_or_else(|| usage_error("Missing program for alu"))?;
+  if trace && debug {
+    return Err(usage_error("--trace and --debug can not be combined"));
+  }
+  if debug && program == "-" {
+    return Err(usage_error(
+      "--debug reads commands from stdin, so the program can not be read from stdin",
+    ));
+  }
+  Ok(Command::Alu { program, inputs, trace, debug })
+}
+
+pub fn parse_args(args: &[String]) -> AocResult<Command> {
+  let (command, rest) = args
+    .split_first()
+    .ok_or_else(|| usage_error("Missing command"))?;
+  match command.as_str() {
+    "run" => parse_run(rest),
+    "run-all" => parse_run_all(rest),
+    "benchmark" => parse_benchmark(rest),
+    "verify" => parse_verify(rest),
+    "alu" => parse_alu(rest),
+    "list" if rest.is_empty() => Ok(Command::List),
+    "list" => Err(usage_error("list takes no arguments")),
+    "help" | "--help" | "-h" => Ok(Command::Help),
+    _ => Err(usage_error(&format!("Unknown command '{}'", command))),
+  }
+}
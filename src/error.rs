@@ -1,6 +1,8 @@
 use core::fmt;
+use std::error::Error;
 use std::io;
 use std::num::ParseIntError;
+use std::str::FromStr;
 
 pub type AocResult<T> = Result<T, AocError>;
 
@@ -9,6 +11,8 @@ pub enum AocError {
   Io(io::Error),
   ParseIntError(ParseIntError),
   ParserError(String),
+  Usage(String),
+  Input(InputError),
   Custom(String),
 }
 
@@ -18,11 +22,34 @@ impl fmt::Display for AocError {
       AocError::Io(ref err) => err.fmt(f),
       AocError::ParseIntError(ref err) => err.fmt(f),
       AocError::ParserError(ref err) => err.fmt(f),
+      AocError::Usage(ref err) => err.fmt(f),
+      AocError::Input(ref err) => err.fmt(f),
       AocError::Custom(ref err) => write!(f, "AoC error: {:?}", err),
     }
   }
 }
 
+impl Error for AocError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      AocError::Io(ref err) => Some(err),
+      AocError::ParseIntError(ref err) => Some(err),
+      AocError::Input(ref err) => err.source(),
+      _ => None,
+    }
+  }
+}
+
+impl AocError {
+  /// Record which day's input the error is about, if it is about an input.
+  pub fn in_day(mut self, day: u32) -> AocError {
+    if let AocError::Input(ref mut err) = self {
+      err.day.get_or_insert(day);
+    }
+    self
+  }
+}
+
 impl From<io::Error> for AocError {
   fn from(err: io::Error) -> AocError { AocError::Io(err) }
 }
@@ -31,6 +58,10 @@ impl From<ParseIntError> for AocError {
   fn from(err: ParseIntError) -> AocError { AocError::ParseIntError(err) }
 }
 
+impl From<InputError> for AocError {
+  fn from(err: InputError) -> AocError { AocError::Input(err) }
+}
+
 impl<E> From<nom::Err<E>> for AocError
 where
   nom::Err<E>: std::fmt::Display,
@@ -39,3 +70,144 @@ where
 }
 
 pub fn aoc_error(msg: &str) -> AocError { AocError::Custom(msg.to_owned()) }
+
+/// A malformed puzzle input, pointing at the offending text.
+#[derive(Debug)]
+pub struct InputError {
+  pub day: Option<u32>,
+  /// 1-based line and column (in characters) of the offending text.
+  pub line: usize,
+  pub column: usize,
+  pub text: String,
+  pub msg: String,
+  /// The whole line containing the offending text, for display.
+  line_text: String,
+  source: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl InputError {
+  /// An error about `at`, which should be a slice of `input` so that its
+  /// position can be found. Other strings are located by searching for them.
+  pub fn new(input: &str, at: &str, msg: impl Into<String>) -> InputError {
+    let start = input.as_ptr() as usize;
+    let offset = match (at.as_ptr() as usize).checked_sub(start) {
+      Some(offset) if offset + at.len() <= input.len() => offset,
+      _ => input.find(at).unwrap_or(0),
+    };
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[offset..]
+      .find('\n')
+      .map_or(input.len(), |i| offset + i);
+    InputError {
+      day: None,
+      line: before.matches('\n').count() + 1,
+      column: before[line_start..].chars().count() + 1,
+      text: at.to_owned(),
+      msg: msg.into(),
+      line_text: input[line_start..line_end]
+        .trim_end_matches('\r')
+        .to_owned(),
+      source: None,
+    }
+  }
+
+  pub fn with_source(mut self, err: impl Error + Send + Sync + 'static) -> InputError {
+    self.source = Some(Box::new(err));
+    self
+  }
+}
+
+impl fmt::Display for InputError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if let Some(day) = self.day {
+      write!(f, "day {}, ", day)?;
+    }
+    write!(
+      f,
+      "line {}, column {}: {}",
+      self.line, self.column, self.msg
+    )?;
+    if !self.text.is_empty() && !self.text.contains('\n') {
+      write!(f, " '{}'", self.text)?;
+    }
+    if !self.line_text.is_empty() {
+      let underline = self
+        .text
+        .lines()
+        .next()
+        .map_or(0, |t| t.chars().count())
+        .max(1);
+      write!(f, "\n  | {}", self.line_text)?;
+      write!(
+        f,
+        "\n  | {}{}",
+        " ".repeat(self.column - 1),
+        "^".repeat(underline)
+      )?;
+    }
+    Ok(())
+  }
+}
+
+impl Error for InputError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    self
+      .source
+      .as_deref()
+      .map(|err| err as &(dyn Error + 'static))
+  }
+}
+
+/// Shorthand for an `InputError` about `at` in `input`.
+pub fn input_error(input: &str, at: &str, msg: impl Into<String>) -> AocError {
+  InputError::new(input, at, msg).into()
+}
+
+/// Parse `token`, a slice of `input`, reporting its location if it is not a
+/// valid `T`.
+pub fn parse_at<T>(input: &str, token: &str) -> AocResult<T>
+where
+  T: FromStr,
+  T::Err: Error + Send + Sync + 'static,
+{
+  token.parse().map_err(|err| {
+    let what = std::any::type_name::<T>()
+      .rsplit("::")
+      .next()
+      .unwrap_or("value");
+    InputError::new(input, token, format!("Invalid {}", what))
+      .with_source(err)
+      .into()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn locates_slice() {
+    let input = "12\n3x4\n";
+    let err = InputError::new(input, &input[4..5], "Unexpected character");
+    assert_eq!((err.line, err.column, err.text.as_str()), (2, 2, "x"));
+    assert_eq!(
+      err.to_string(),
+      "line 2, column 2: Unexpected character 'x'\n  | 3x4\n  |  ^"
+    );
+  }
+
+  #[test]
+  fn parse_error_has_day_and_source() {
+    let input = "199\n2OO\n";
+    let err = parse_at::<i32>(input, &input[4..7]).unwrap_err().in_day(1);
+    match &err {
+      AocError::Input(err) => assert_eq!((err.day, err.line, err.column), (Some(1), 2, 1)),
+      _ => panic!("expected an input error, got {:?}", err),
+    }
+    assert!(err
+      .to_string()
+      .starts_with("day 1, line 2, column 1: Invalid i32 '2OO'"));
+    assert!(err.source().is_some());
+  }
+}
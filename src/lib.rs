@@ -1,4 +1,11 @@
+pub mod alu;
+pub mod answers;
+pub mod baseline;
+pub mod bench;
+pub mod cli;
 pub mod error;
+pub mod parallel;
+pub mod report;
 pub mod solutions;
 pub mod time_it;
 pub mod utils;
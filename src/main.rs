@@ -1,159 +1,467 @@
-use std::fmt::Display;
+use std::error::Error;
+use std::path::Path;
+use std::process;
 use std::time::Duration;
 
+use aoc::alu::{self, Machine};
+use aoc::answers::{self, Answers};
+use aoc::baseline::{self, Comparison, Verdict};
+use aoc::bench::{self, BenchOptions, DayBench, RunAll, Stats};
+use aoc::cli::{self, Command, Part};
 use aoc::error::AocError;
-use aoc::solutions;
+use aoc::report::{self, Format};
+use aoc::{parallel, solutions};
 use itertools::Itertools;
 
-// Rust macros are weird and fun
-macro_rules! aoc_benchmark {
-  (@step $_idx:expr, @outvec $_times: ident,) => {};
-
-  (@step $idx:expr, @outvec $times: ident, $head:path, $($tail:path,)*) => {
-    let day = format!("{:02}", $idx);
-    let input_file = format!("inputs/{}input", day);
-    $times.push((day, time_it!($head(&input_file)?, 10).elapsed,));
-
-    aoc_benchmark!(@step $idx + 1usize, @outvec $times, $($tail,)*);
-  };
-
-  ($($n:path),* $(,)?) => {{
-    let mut times = vec![];
-    aoc_benchmark!(@step 1usize, @outvec times, $($n,)*);
-    times
-  }}
+/// Format a duration with three significant-ish digits in a fitting unit.
+fn fmt_duration(d: Duration) -> String {
+  let ns = d.as_nanos() as f64;
+  if ns < 1e3 {
+    format!("{:.0}ns", ns)
+  } else if ns < 1e6 {
+    format!("{:.2}µs", ns / 1e3)
+  } else if ns < 1e9 {
+    format!("{:.2}ms", ns / 1e6)
+  } else {
+    format!("{:.2}s", ns / 1e9)
+  }
 }
 
-macro_rules! time_it {
-  ($f: expr, $n: expr) => {{
-    let mut times = vec![];
+fn benchmark_all(
+  only: &[u32],
+  opts: &BenchOptions,
+  format: Format,
+  save_baseline: Option<&str>,
+  compare: Option<&str>,
+  threshold: f64,
+) -> Result<(), AocError> {
+  // Load the baseline and check the name to save under first, so a typo does
+  // not waste a whole benchmark run.
+  let compare = match compare {
+    Some(name) => Some((name, baseline::load(name)?)),
+    None => None,
+  };
+  if let Some(name) = save_baseline {
+    baseline::path(name)?;
+  }
 
-    let t = std::time::Instant::now();
-    let (res1, res2) = $f;
-    times.push(t.elapsed());
+  let results = solutions::filter(only)
+    .map(|solution| {
+      let input = cli::read_input(&cli::default_input(solution.day()))?;
+      bench::benchmark(solution, &input, opts)
+    })
+    .collect::<Result<Vec<_>, AocError>>()?;
+  match format {
+    Format::Table => print_table(&results, opts.trim),
+    Format::Json => println!("{}", report::bench_json(&results, opts.trim)),
+    Format::Csv => println!("{}", report::bench_csv(&results, opts.trim)),
+  }
 
-    for _ in 1..$n {
-      let t = std::time::Instant::now();
-      $f;
-      times.push(t.elapsed());
+  if let Some(name) = save_baseline {
+    baseline::save(name, &results)?;
+  }
+  if let Some((name, base)) = compare {
+    let comparisons = baseline::compare(&base, &results, threshold);
+    let table = comparison_table(name, &comparisons);
+    // Keep stdout parseable for scripts.
+    if format == Format::Table {
+      println!("{}", table);
+    } else {
+      eprintln!("{}", table);
     }
-    RunResult {
-      elapsed: times,
-      part1_result: Box::new(res1),
-      part2_result: Box::new(res2),
+    let regressions = comparisons
+      .iter()
+      .filter(|c| c.verdict == Verdict::Regression)
+      .count();
+    if regressions > 0 {
+      return Err(AocError::Custom(format!(
+        "{} day(s) regressed compared to baseline '{}'",
+        regressions, name
+      )));
     }
-    //let t = std::time::Instant::now();
-    //let (res1, res2) = $f;
-    //for _ in 1..$n {
-    //  $f;
-    //}
-    //let elapsed = t.elapsed();
-    //RunResult {
-    //  avg_elapsed: elapsed / $n,
-    //  part1_result: Box::new(res1),
-    //  part2_result: Box::new(res2),
-    //}
-  }};
+  }
+  Ok(())
 }
 
-struct RunResult {
-  elapsed: Vec<Duration>,
-  part1_result: Box<dyn Display>,
-  part2_result: Box<dyn Display>,
+fn comparison_table(name: &str, comparisons: &[Comparison]) -> String {
+  let mut lines = vec![format!(
+    "{:>3}  {:>9}  {:>9}  {:>8}  (baseline '{}')",
+    "day", "baseline", "current", "change", name
+  )];
+  for c in comparisons {
+    let verdict = match c.verdict {
+      Verdict::Regression => "regression",
+      Verdict::Improvement => "improvement",
+      Verdict::Unchanged => "",
+    };
+    lines.push(
+      format!(
+        "{:>3}  {:>9}  {:>9}  {:>+7.1}%  {}",
+        format!("{:02}", c.day),
+        fmt_duration(c.baseline.mean),
+        fmt_duration(c.current.mean),
+        100.0 * c.change,
+        verdict
+      )
+      .trim_end()
+      .to_owned(),
+    );
+  }
+  lines.join("\n")
 }
 
-impl RunResult {
-  fn avg_elapsed(&self) -> Duration {
-    self.elapsed.iter().sum::<Duration>() / (self.elapsed.len() as u32)
+fn print_table(results: &[DayBench], trim: f64) {
+  if results.is_empty() {
+    return;
+  }
+
+  fn print_row(name: &str, cells: &[Duration], n: &str, bar: &str) {
+    let cells = cells
+      .iter()
+      .map(|&d| format!("{:>9}", fmt_duration(d)))
+      .join(" ");
+    println!("{:>3} │{} {:>6} │{}", name, cells, n, bar);
   }
+
+  let width = 20.0;
+  let max = results.iter().map(|r| r.stats.median).max().unwrap();
+  let line = "─".repeat(8 * 10 + 7);
+
+  println!();
+  println!("    ╭{}╮", line);
+  println!(
+    "    │{:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>6} │",
+    "parse", "solve", "cpu", "min", "median", "mean", "stddev", "p95", "n"
+  );
+  println!("    ├{}┤", line);
+  for r in results {
+    let s = &r.stats;
+    let parse = r.stats_of(|t| t.parse, trim).median;
+    let solve = r.stats_of(|t| t.solve(), trim).median;
+    let cpu = r.stats_of(|t| t.cpu, trim).median;
+    let x = (2.0 * width * s.median.as_secs_f64() / max.as_secs_f64()) as usize;
+    let bar = "─".repeat(x / 2) + if x % 2 == 1 { "╴" } else { "" };
+    let bar = if bar.is_empty() { bar } else { format!(" {}", bar) };
+    print_row(
+      &format!("{:02}", r.day),
+      &[parse, solve, cpu, s.min, s.median, s.mean, s.stddev, s.p95],
+      &s.n.to_string(),
+      &bar,
+    );
+  }
+  println!("    ├{}┤", line);
+  let sum = |f: fn(&Stats) -> Duration| results.iter().map(|r| f(&r.stats)).sum::<Duration>();
+  let parse = results
+    .iter()
+    .map(|r| r.stats_of(|t| t.parse, trim).median)
+    .sum();
+  let solve = results
+    .iter()
+    .map(|r| r.stats_of(|t| t.solve(), trim).median)
+    .sum();
+  let cpu = results
+    .iter()
+    .map(|r| r.stats_of(|t| t.cpu, trim).median)
+    .sum();
+  // Assuming independent days, variances add up.
+  let stddev = results
+    .iter()
+    .map(|r| r.stats.stddev.as_secs_f64().powi(2))
+    .sum::<f64>()
+    .sqrt();
+  print_row(
+    "Sum",
+    &[
+      parse,
+      solve,
+      cpu,
+      sum(|s| s.min),
+      sum(|s| s.median),
+      sum(|s| s.mean),
+      Duration::from_secs_f64(stddev),
+      sum(|s| s.p95),
+    ],
+    "",
+    "",
+  );
+  println!("    ╰{}╯", line);
+  println!("     Times are wall-clock, except cpu: the CPU time of all threads.");
+  println!();
 }
 
-fn run_all() -> Result<(), AocError> {
-  let times = aoc_benchmark!(
-    solutions::day01::run,
-    solutions::day02::run,
-    solutions::day03::run,
-    solutions::day04::run,
-    solutions::day05::run,
-    solutions::day06::run,
-    solutions::day07::run,
-    solutions::day08::run,
-    solutions::day09::run,
-    solutions::day10::run,
-    solutions::day11::run,
-    solutions::day12::run,
-    solutions::day13::run,
-    solutions::day14::run,
-    solutions::day15::run,
-    solutions::day16::run,
-    solutions::day17::run,
-    solutions::day18::run,
-    solutions::day19::run,
-    solutions::day20::run,
-    solutions::day21::run,
-    solutions::day22::run,
-    solutions::day23::run,
-    solutions::day24::run,
-    solutions::day25::run,
+fn solve_all(only: &[u32], jobs: usize, format: Format) -> Result<(), AocError> {
+  let solutions = solutions::filter(only).collect_vec();
+  let all = bench::run_all(&solutions, jobs);
+
+  let summary = format!(
+    "{} day(s) in {} wall-clock, {} CPU on {} job(s) ({:.1}x parallelism)",
+    all.runs.len(),
+    fmt_duration(all.wall),
+    fmt_duration(all.cpu),
+    all.jobs,
+    all.cpu.as_secs_f64() / all.wall.as_secs_f64().max(f64::MIN_POSITIVE),
   );
+  match format {
+    Format::Table => {
+      print_answers(&all);
+      println!("\n{}", summary);
+    }
+    Format::Json => println!("{}", report::run_all_json(&all)),
+    Format::Csv => {
+      println!("{}", report::run_all_csv(&all));
+      eprintln!("{}", summary);
+    }
+  }
 
-  fn print_row(name: &str, elapsed: Duration, total: Duration, max: Duration) {
-    let width = 20.0;
-    let fraction = elapsed.as_secs_f32() / total.as_secs_f32();
-    let max_fraction = elapsed.as_secs_f32() / max.as_secs_f32();
-
-    let ms = elapsed.as_secs_f64() * 1e3;
-    if fraction < 1.0 {
-      let x = (2.0 * width * max_fraction) as usize;
-      let bar = "─".repeat(x / 2) + if x % 2 == 1 { "╴" } else { "" };
-      println!(
-        "{:>3} │{:9.3}ms {:6.2}% ├{}",
-        name,
-        ms,
-        100.0 * fraction,
-        bar,
-      );
-    } else {
-      println!("{:>3} │{:9.3}ms {:6.2}% │", name, ms, 100.0 * fraction,);
+  let failed = all.runs.iter().filter(|(_, _, res)| res.is_err()).count();
+  if failed > 0 {
+    return Err(AocError::Custom(format!("{} day(s) failed", failed)));
+  }
+  Ok(())
+}
+
+fn print_answers(all: &RunAll) {
+  println!(
+    "day  {:<25}  {:>9}  {:<16} part 2",
+    "title", "wall", "part 1"
+  );
+  let mut errors = vec![];
+  for (day, title, res) in &all.runs {
+    let run = match res {
+      Ok(run) => run,
+      Err(err) => {
+        println!(
+          "{:>3}  {:<25}  {:>9}  error",
+          format!("{:02}", day),
+          title,
+          ""
+        );
+        errors.push(format!("day {}: {}", day, err));
+        continue;
+      }
+    };
+    // Multi-line answers (ASCII art) go below the row.
+    let mut below = vec![];
+    let mut cell = |answer: &Option<String>| match answer.as_deref() {
+      Some(a) if a.contains('\n') => {
+        below.push(a.to_owned());
+        "(below)".to_owned()
+      }
+      a => a.unwrap_or_default().to_owned(),
+    };
+    let (part1, part2) = (cell(&run.part1), cell(&run.part2));
+    println!(
+      "{:>3}  {:<25}  {:>9}  {:<16} {}",
+      format!("{:02}", day),
+      title,
+      fmt_duration(run.timing.total()),
+      part1,
+      part2
+    );
+    for answer in below {
+      for line in answer.lines().filter(|l| !l.trim().is_empty()) {
+        println!("     {}", line.trim_end());
+      }
     }
   }
+  for error in errors {
+    println!("\n{}", error);
+  }
+}
 
-  fn average(times: Vec<Duration>) -> Duration {
-    println!("{:?}", times);
-    let n_outliers = 0; // (times.len() + 9)/10; // Remove top/bottom 10%
-    let n_keep = times.len() - 2*n_outliers;
-    times.into_iter().sorted().skip(n_outliers).take(n_keep).sum::<Duration>()/(n_keep as u32)
+fn run_one(
+  day: u32,
+  input: Option<String>,
+  part: Option<Part>,
+  format: Format,
+  trace: bool,
+) -> Result<(), AocError> {
+  let solution =
+    solutions::get(day).ok_or_else(|| cli::usage_error(&format!("No solution for day {}", day)))?;
+  let input = cli::read_input(&input.unwrap_or_else(|| cli::default_input(day)))?;
+  let res = bench::run(solution, &input, part)?;
+  let trace = if trace {
+    let trace = solution.trace(&solution.parse(&input)?)?;
+    Some(trace.ok_or_else(|| AocError::Custom(format!("Day {} has no trace", day)))?)
+  } else {
+    None
+  };
+  match format {
+    Format::Table => {}
+    Format::Json | Format::Csv => {
+      if format == Format::Json {
+        println!("{}", report::run_json(day, solution.title(), &res));
+      } else {
+        println!("{}", report::run_csv(day, solution.title(), &res));
+      }
+      // Keep stdout parseable for scripts.
+      if let Some(trace) = trace {
+        eprintln!("{}", trace);
+      }
+      return Ok(());
+    }
   }
+  if let Some(trace) = trace {
+    println!("{}", trace);
+  }
+  if let Some(answer) = res.part1 {
+    println!("Part 1: {}", answer);
+  }
+  if let Some(answer) = res.part2 {
+    println!("Part 2: {}", answer);
+  }
+  let timing = res.timing;
+  println!(
+    "Elapsed: {} (parse {}, part 1 {}, part 2 {})",
+    fmt_duration(timing.total()),
+    fmt_duration(timing.parse),
+    fmt_duration(timing.part1),
+    fmt_duration(timing.part2),
+  );
+  Ok(())
+}
 
-  let times = times
-    .into_iter()
-    .map(|(label, x)| (label, average(x)))
-    .collect_vec();
+fn verify(only: &[u32], answers_path: &str) -> Result<(), AocError> {
+  let answers = Answers::load(answers_path)?;
+  let (mut passed, mut failed, mut missing) = (0, 0, 0);
+  let mut failures = vec![];
 
-  println!("");
-  println!("    ╭────────────────────╮");
-  let total: Duration = times.iter().map(|(_, e)| e).sum();
-  let max: Duration = times.iter().map(|(_, e)| e).copied().max().unwrap();
-  for (name, elapsed) in times {
-    print_row(&name, elapsed, total, max);
+  println!("day  {:<25}  {:<8} part 2", "title", "part 1");
+  for solution in solutions::filter(only) {
+    let day = solution.day();
+    let input_file = cli::default_input(day);
+    let run = match std::fs::read_to_string(&input_file) {
+      Ok(input) => Some(bench::run(solution, &input, None)),
+      Err(_) => None,
+    };
+
+    let mut status = vec![];
+    for part in [Part::One, Part::Two] {
+      status.push(match (answers.get(day, part), &run) {
+        (None, _) => {
+          missing += 1;
+          "missing"
+        }
+        (Some(_), None) => {
+          missing += 1;
+          "no input"
+        }
+        (Some(expected), Some(run)) => {
+          let got = match run {
+            Ok(run) if part == Part::One => run.part1.clone().unwrap_or_default(),
+            Ok(run) => run.part2.clone().unwrap_or_default(),
+            Err(err) => format!("error: {}", err),
+          };
+          if answers::matches(expected, &got) {
+            passed += 1;
+            "pass"
+          } else {
+            failed += 1;
+            failures.push(format!(
+              "day {} part {}: expected {}, got {}",
+              day, part, expected, got
+            ));
+            "FAIL"
+          }
+        }
+      });
+    }
+    println!(
+      "{:>3}  {:<25}  {:<8} {}",
+      format!("{:02}", day),
+      solution.title(),
+      status[0],
+      status[1]
+    );
   }
-  println!("    ├────────────────────┤");
-  print_row("Sum", total, total, max);
-  println!("    ╰────────────────────╯");
 
-  println!("");
+  for failure in &failures {
+    println!("\n{}", failure);
+  }
+  println!(
+    "\n{} passed, {} failed, {} missing",
+    passed, failed, missing
+  );
+  if failed > 0 {
+    return Err(AocError::Custom(format!(
+      "{} answer(s) did not match {}",
+      failed, answers_path
+    )));
+  }
   Ok(())
 }
 
-fn main() -> Result<(), AocError> {
-  let args: Vec<String> = std::env::args().collect();
-  if args[1] == "benchmark" {
-    run_all()?;
+fn list() {
+  for solution in solutions::all() {
+    let input_file = cli::default_input(solution.day());
+    let status = if Path::new(&input_file).exists() { "" } else { " (missing input)" };
+    println!("{:2}  {:<25}{}", solution.day(), solution.title(), status);
+  }
+}
+
+fn run_alu(path: &str, inputs: &[i64], trace: bool, debug: bool) -> Result<(), AocError> {
+  let program = alu::parse_program(&cli::read_input(path)?)?;
+  let mut stdout = std::io::stdout();
+  if debug {
+    alu::debug(&program, inputs, std::io::stdin().lock(), &mut stdout)?;
+    return Ok(());
+  }
+  let mut machine = Machine::new();
+  let mut inputs = inputs.iter().copied();
+  if trace {
+    machine.trace(&program, &mut inputs, &mut stdout.lock())?;
   } else {
-    let res = time_it!(solutions::day25::run(&args[1])?, 1);
-    println!("Part 1: {}", res.part1_result);
-    println!("Part 2: {}", res.part2_result);
-    println!("Elapsed: {:.3}ms", res.avg_elapsed().as_secs_f64() * 1e3);
+    machine.run(&program, &mut inputs)?;
   }
+  println!("{}", machine);
   Ok(())
 }
+
+fn run(args: &[String]) -> Result<(), AocError> {
+  match cli::parse_args(args)? {
+    Command::Run { day, input, part, format, parallel, trace } => {
+      parallel::set_intra_day(parallel);
+      run_one(day, input, part, format, trace)?
+    }
+    Command::RunAll { days, jobs, format, parallel } => {
+      parallel::set_intra_day(parallel);
+      solve_all(&days, jobs, format)?
+    }
+    Command::Benchmark { days, options, format, parallel, save_baseline, compare, threshold } => {
+      parallel::set_intra_day(parallel);
+      benchmark_all(
+        &days,
+        &options,
+        format,
+        save_baseline.as_deref(),
+        compare.as_deref(),
+        threshold,
+      )?
+    }
+    Command::Verify { days, answers } => verify(&days, &answers)?,
+    Command::Alu { program, inputs, trace, debug } => run_alu(&program, &inputs, trace, debug)?,
+    Command::List => list(),
+    Command::Help => println!("{}", cli::USAGE),
+  }
+  Ok(())
+}
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  match run(&args) {
+    Ok(()) => {}
+    Err(AocError::Usage(msg)) => {
+      eprintln!("error: {}\n\n{}", msg, cli::USAGE);
+      process::exit(2);
+    }
+    Err(err) => {
+      eprintln!("error: {}", err);
+      let mut source = err.source();
+      while let Some(cause) = source {
+        eprintln!("caused by: {}", cause);
+        source = cause.source();
+      }
+      process::exit(1);
+    }
+  }
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Whether expensive days may spread their own work over several threads.
+static INTRA_DAY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_intra_day(enabled: bool) { INTRA_DAY.store(enabled, Ordering::Relaxed) }
+
+pub fn intra_day() -> bool { INTRA_DAY.load(Ordering::Relaxed) }
+
+/// Number of threads the machine can run in parallel.
+pub fn available_jobs() -> usize { std::thread::available_parallelism().map_or(1, |n| n.get()) }
+
+/// Apply `f` to all items on up to `jobs` threads, returning the results in
+/// the order of the items. Threads pick the next unprocessed item whenever
+/// they are done, so uneven items still keep all threads busy.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+  T: Sync,
+  R: Send,
+  F: Fn(&T) -> R + Sync,
+{
+  let jobs = jobs.clamp(1, items.len().max(1));
+  if jobs == 1 {
+    return items.iter().map(f).collect();
+  }
+
+  let next = AtomicUsize::new(0);
+  let results = Mutex::new(Vec::with_capacity(items.len()));
+  std::thread::scope(|scope| {
+    for _ in 0..jobs {
+      scope.spawn(|| {
+        let mut done = vec![];
+        loop {
+          let i = next.fetch_add(1, Ordering::Relaxed);
+          match items.get(i) {
+            Some(item) => done.push((i, f(item))),
+            None => break,
+          }
+        }
+        results.lock().unwrap().extend(done);
+      });
+    }
+  });
+
+  let mut results = results.into_inner().unwrap();
+  results.sort_unstable_by_key(|&(i, _)| i);
+  results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// `map` for use inside a day: parallel on all cores if intra-day
+/// parallelism is enabled, sequential otherwise.
+pub fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+  T: Sync,
+  R: Send,
+  F: Fn(&T) -> R + Sync,
+{
+  let jobs = if intra_day() { available_jobs() } else { 1 };
+  map(items, jobs, f)
+}
+
+/// CPU time used so far by the whole process, summed over all threads.
+/// `None` where the platform does not tell.
+#[cfg(unix)]
+pub fn cpu_time() -> Option<Duration> {
+  let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+  // SAFETY: getrusage only writes to the struct we pass it.
+  if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+    return None;
+  }
+  // SAFETY: getrusage succeeded, so the struct is initialized.
+  let usage = unsafe { usage.assume_init() };
+  let time = |t: libc::timeval| Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000);
+  Some(time(usage.ru_utime) + time(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+pub fn cpu_time() -> Option<Duration> { None }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn map_keeps_order() {
+    let items = (0..100u64).collect::<Vec<_>>();
+    for jobs in [1, 3, 16] {
+      let squares = map(&items, jobs, |x| x * x);
+      assert_eq!(squares, items.iter().map(|x| x * x).collect::<Vec<_>>());
+    }
+    assert!(map(&[] as &[u64], 4, |x| *x).is_empty());
+  }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::bench::{DayBench, Run, RunAll, Stats, Timing};
+use crate::error::{AocError, AocResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+  #[default]
+  Table,
+  Json,
+  Csv,
+}
+
+impl std::str::FromStr for Format {
+  type Err = AocError;
+  fn from_str(s: &str) -> AocResult<Format> {
+    match s {
+      "table" => Ok(Format::Table),
+      "json" => Ok(Format::Json),
+      "csv" => Ok(Format::Csv),
+      _ => Err(AocError::Usage(format!(
+        "Invalid format '{}', expected table, json or csv",
+        s
+      ))),
+    }
+  }
+}
+
+/// A phase of a run, with the name used as key in the output.
+type Phase = (&'static str, fn(&Timing) -> Duration);
+
+const PHASES: [Phase; 5] = [
+  ("parse", |t| t.parse),
+  ("part1", |t| t.part1),
+  ("part2", |t| t.part2),
+  ("total", |t| t.total()),
+  ("cpu", |t| t.cpu),
+];
+
+fn json_str(s: &str) -> String {
+  let mut res = String::from("\"");
+  for c in s.chars() {
+    match c {
+      '"' => res += "\\\"",
+      '\\' => res += "\\\\",
+      '\n' => res += "\\n",
+      '\t' => res += "\\t",
+      c if (c as u32) < 0x20 => write!(res, "\\u{:04x}", c as u32).unwrap(),
+      c => res.push(c),
+    }
+  }
+  res.push('"');
+  res
+}
+
+fn json_opt_str(s: &Option<String>) -> String { s.as_deref().map_or("null".to_owned(), json_str) }
+
+fn csv_field(s: &str) -> String {
+  if s.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", s.replace('"', "\"\""))
+  } else {
+    s.to_owned()
+  }
+}
+
+fn stats_json(s: &Stats) -> String {
+  format!(
+    "{{\"n\": {}, \"min_ns\": {}, \"median_ns\": {}, \"mean_ns\": {}, \"stddev_ns\": {}, \
+     \"p95_ns\": {}}}",
+    s.n,
+    s.min.as_nanos(),
+    s.median.as_nanos(),
+    s.mean.as_nanos(),
+    s.stddev.as_nanos(),
+    s.p95.as_nanos()
+  )
+}
+
+/// A single run as a JSON object. Skipped parts have `null` answers.
+pub fn run_json(day: u32, title: &str, run: &Run) -> String {
+  let timings = PHASES
+    .iter()
+    .map(|(name, f)| format!("\"{}\": {}", name, f(&run.timing).as_nanos()))
+    .collect::<Vec<_>>()
+    .join(", ");
+  format!(
+    "{{\"day\": {}, \"title\": {}, \"answers\": {{\"part1\": {}, \"part2\": {}}}, \
+     \"timings_ns\": {{{}}}}}",
+    day,
+    json_str(title),
+    json_opt_str(&run.part1),
+    json_opt_str(&run.part2),
+    timings
+  )
+}
+
+const RUN_CSV_HEADER: &str = "day,title,part1,part2,parse_ns,part1_ns,part2_ns,total_ns,cpu_ns";
+
+fn run_csv_row(day: u32, title: &str, run: &Run) -> String {
+  let mut row = vec![
+    day.to_string(),
+    csv_field(title),
+    csv_field(run.part1.as_deref().unwrap_or("")),
+    csv_field(run.part2.as_deref().unwrap_or("")),
+  ];
+  row.extend(
+    PHASES
+      .iter()
+      .map(|(_, f)| f(&run.timing).as_nanos().to_string()),
+  );
+  row.join(",")
+}
+
+/// A single run as CSV with a header line. Skipped parts have empty answers.
+pub fn run_csv(day: u32, title: &str, run: &Run) -> String {
+  format!("{}\n{}", RUN_CSV_HEADER, run_csv_row(day, title, run))
+}
+
+/// Results of `run-all` as a JSON object with the totals and one object per
+/// day. Failed days have an `error` instead of answers and timings.
+pub fn run_all_json(all: &RunAll) -> String {
+  let days = all
+    .runs
+    .iter()
+    .map(|(day, title, res)| match res {
+      Ok(run) => format!("  {}", run_json(*day, title, run)),
+      Err(err) => format!(
+        "  {{\"day\": {}, \"title\": {}, \"error\": {}}}",
+        day,
+        json_str(title),
+        json_str(&err.to_string())
+      ),
+    })
+    .collect::<Vec<_>>();
+  format!(
+    "{{\"jobs\": {}, \"wall_ns\": {}, \"cpu_ns\": {}, \"days\": [\n{}\n]}}",
+    all.jobs,
+    all.wall.as_nanos(),
+    all.cpu.as_nanos(),
+    days.join(",\n")
+  )
+}
+
+/// Results of `run-all` as CSV, one row per day with an extra `error` column.
+pub fn run_all_csv(all: &RunAll) -> String {
+  let mut lines = vec![format!("{},error", RUN_CSV_HEADER)];
+  for (day, title, res) in &all.runs {
+    lines.push(match res {
+      Ok(run) => format!("{},", run_csv_row(*day, title, run)),
+      Err(err) => format!(
+        "{},{},,,,,,,,{}",
+        day,
+        csv_field(title),
+        csv_field(&err.to_string())
+      ),
+    });
+  }
+  lines.join("\n")
+}
+
+/// Benchmark results as a JSON array with one object per day, holding
+/// statistics for every phase.
+pub fn bench_json(results: &[DayBench], trim: f64) -> String {
+  let days = results
+    .iter()
+    .map(|r| {
+      let stats = PHASES
+        .iter()
+        .map(|(name, f)| format!("\"{}\": {}", name, stats_json(&r.stats_of(f, trim))))
+        .collect::<Vec<_>>()
+        .join(",\n      ");
+      format!(
+        "  {{\n    \"day\": {},\n    \"title\": {},\n    \"answers\": {{\"part1\": {}, \"part2\": \
+         {}}},\n    \"stats\": {{\n      {}\n    }}\n  }}",
+        r.day,
+        json_str(r.title),
+        json_str(&r.part1),
+        json_str(&r.part2),
+        stats
+      )
+    })
+    .collect::<Vec<_>>();
+  format!("[\n{}\n]", days.join(",\n"))
+}
+
+/// Benchmark results as CSV in long form, one row per day and phase.
+pub fn bench_csv(results: &[DayBench], trim: f64) -> String {
+  let mut lines =
+    vec!["day,title,part1,part2,phase,n,min_ns,median_ns,mean_ns,stddev_ns,p95_ns".to_owned()];
+  for r in results {
+    for (name, f) in PHASES {
+      let s = r.stats_of(f, trim);
+      lines.push(format!(
+        "{},{},{},{},{},{},{},{},{},{},{}",
+        r.day,
+        csv_field(r.title),
+        csv_field(&r.part1),
+        csv_field(&r.part2),
+        name,
+        s.n,
+        s.min.as_nanos(),
+        s.median.as_nanos(),
+        s.mean.as_nanos(),
+        s.stddev.as_nanos(),
+        s.p95.as_nanos()
+      ));
+    }
+  }
+  lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn json_escapes() {
+    assert_eq!(json_str("1564"), r#""1564""#);
+    assert_eq!(json_str(r#"say "hi""#), r#""say \"hi\"""#);
+    assert_eq!(json_str(r"a\b"), r#""a\\b""#);
+    assert_eq!(json_str("#..#\n#..#"), r##""#..#\n#..#""##);
+    assert_eq!(json_str("a\tb"), r#""a\tb""#);
+    assert_eq!(json_str("a\rb\u{1}\u{1f}"), r#""a\u000db\u0001\u001f""#);
+    assert_eq!(json_str("ünï ☃"), "\"ünï ☃\"");
+    assert_eq!(json_opt_str(&None), "null");
+    assert_eq!(json_opt_str(&Some("x".to_owned())), r#""x""#);
+  }
+
+  #[test]
+  fn csv_quoting() {
+    assert_eq!(csv_field("1564"), "1564");
+    assert_eq!(csv_field("Sonar Sweep"), "Sonar Sweep");
+    assert_eq!(csv_field("a,b"), r#""a,b""#);
+    assert_eq!(csv_field(r#"say "hi""#), r#""say ""hi""""#);
+    assert_eq!(csv_field("#..#\n#..#"), "\"#..#\n#..#\"");
+    assert_eq!(csv_field("a\rb"), "\"a\rb\"");
+  }
+}
@@ -1,3 +1,8 @@
+use std::any::Any;
+use std::fmt::Display;
+
+use crate::error::{aoc_error, AocResult};
+
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -23,3 +28,99 @@ pub mod day22;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+
+/// Typed interface implemented by each day's `Solver`.
+///
+/// `parse` turns the puzzle text into `Input`, which is then shared by both parts.
+pub trait Day: Sync {
+  const DAY: u32;
+  const TITLE: &'static str;
+  type Input: Send + Sync + 'static;
+
+  fn parse(input: &str) -> AocResult<Self::Input>;
+  fn part1(input: &Self::Input) -> AocResult<impl Display>;
+  fn part2(input: &Self::Input) -> AocResult<impl Display>;
+
+  /// A readable account of how the answers come about, like the path taken,
+  /// for days that have one. Shown by `run --trace`.
+  fn trace(_input: &Self::Input) -> AocResult<Option<String>> { Ok(None) }
+}
+
+/// Parsed input of some day, only meaningful to the `Solution` that produced it.
+pub type ParsedInput = Box<dyn Any + Send + Sync>;
+
+/// Type erased version of `Day`, so all days can live in one registry.
+pub trait Solution: Sync {
+  fn day(&self) -> u32;
+  fn title(&self) -> &'static str;
+  fn parse(&self, input: &str) -> AocResult<ParsedInput>;
+  fn part1(&self, input: &ParsedInput) -> AocResult<String>;
+  fn part2(&self, input: &ParsedInput) -> AocResult<String>;
+  fn trace(&self, input: &ParsedInput) -> AocResult<Option<String>>;
+
+  /// Parse the puzzle text and solve both parts.
+  fn solve_str(&self, input: &str) -> AocResult<(String, String)> {
+    let input = self.parse(input)?;
+    Ok((self.part1(&input)?, self.part2(&input)?))
+  }
+}
+
+impl<D: Day> Solution for D {
+  fn day(&self) -> u32 { D::DAY }
+  fn title(&self) -> &'static str { D::TITLE }
+  fn parse(&self, input: &str) -> AocResult<ParsedInput> {
+    Ok(Box::new(D::parse(input).map_err(|err| err.in_day(D::DAY))?))
+  }
+  fn part1(&self, input: &ParsedInput) -> AocResult<String> {
+    Ok(D::part1(downcast::<D>(input)?)?.to_string())
+  }
+  fn part2(&self, input: &ParsedInput) -> AocResult<String> {
+    Ok(D::part2(downcast::<D>(input)?)?.to_string())
+  }
+  fn trace(&self, input: &ParsedInput) -> AocResult<Option<String>> {
+    D::trace(downcast::<D>(input)?)
+  }
+}
+
+fn downcast<D: Day>(input: &ParsedInput) -> AocResult<&D::Input> {
+  input
+    .downcast_ref::<D::Input>()
+    .ok_or_else(|| aoc_error(&format!("Input was not parsed by day {}", D::DAY)))
+}
+
+pub static SOLUTIONS: [&dyn Solution; 25] = [
+  &day01::Solver,
+  &day02::Solver,
+  &day03::Solver,
+  &day04::Solver,
+  &day05::Solver,
+  &day06::Solver,
+  &day07::Solver,
+  &day08::Solver,
+  &day09::Solver,
+  &day10::Solver,
+  &day11::Solver,
+  &day12::Solver,
+  &day13::Solver,
+  &day14::Solver,
+  &day15::Solver,
+  &day16::Solver,
+  &day17::Solver,
+  &day18::Solver,
+  &day19::Solver,
+  &day20::Solver,
+  &day21::Solver,
+  &day22::Solver,
+  &day23::Solver,
+  &day24::Solver,
+  &day25::Solver,
+];
+
+pub fn all() -> impl Iterator<Item = &'static dyn Solution> { SOLUTIONS.iter().copied() }
+
+pub fn get(day: u32) -> Option<&'static dyn Solution> { all().find(|s| s.day() == day) }
+
+/// The solutions for the given days, or all of them if `days` is empty.
+pub fn filter(days: &[u32]) -> impl Iterator<Item = &'static dyn Solution> + '_ {
+  all().filter(move |s| days.is_empty() || days.contains(&s.day()))
+}
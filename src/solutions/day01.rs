@@ -1,24 +1,62 @@
 use std::fmt::Display;
 
-use crate::error::AocResult;
-
-fn solve(fname: &str) -> AocResult<(i32, i32)> {
-  let s = crate::utils::read_or_die(fname);
-  let ints: Vec<i32> = s
-    .trim()
-    .lines()
-    .map(|x| x.parse::<i32>().unwrap())
-    .collect();
-  let res1 = ints
+use crate::error::{parse_at, AocResult};
+use crate::solutions::Day;
+
+pub fn parse(s: &str) -> AocResult<Vec<i32>> { s.trim().lines().map(|x| parse_at(s, x)).collect() }
+
+fn count_increases(ints: &[i32]) -> i32 {
+  ints
     .windows(2)
     .map(|w| if w[1] > w[0] { 1 } else { 0 })
-    .sum::<i32>();
+    .sum::<i32>()
+}
+
+pub fn part1(ints: &[i32]) -> AocResult<i32> { Ok(count_increases(ints)) }
+
+pub fn part2(ints: &[i32]) -> AocResult<i32> {
   let three_ints: Vec<i32> = ints.windows(3).map(|x| x.iter().sum()).collect();
-  let res2 = three_ints
-    .windows(2)
-    .map(|w| if w[1] > w[0] { 1 } else { 0 })
-    .sum::<i32>();
-  Ok((res1, res2))
+  Ok(count_increases(&three_ints))
+}
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 1;
+  const TITLE: &'static str = "Sonar Sweep";
+  type Input = Vec<i32>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> { solve(fname) }
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+199
+200
+208
+210
+200
+207
+240
+269
+260
+263
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 7);
+    assert_eq!(part2(&input).unwrap(), 5);
+  }
+}
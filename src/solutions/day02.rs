@@ -1,8 +1,7 @@
 use std::fmt::Display;
 
-use crate::error::AocResult;
-
-fn read_or_die(fname: &str) -> String { std::fs::read_to_string(fname).unwrap() }
+use crate::error::{input_error, parse_at, AocResult};
+use crate::solutions::Day;
 
 #[derive(Debug)]
 struct Point {
@@ -23,42 +22,79 @@ macro_rules! pt {
   };
 }
 
-fn do_parse(s: &str) -> (&str, i32) {
+fn do_parse(input: &str, s: &str) -> AocResult<(String, i32)> {
   match s.split(' ').collect::<Vec<&str>>()[..] {
-    [cmd, i] => (cmd, i.parse::<i32>().unwrap()),
-    _ => panic!("Parse failed"),
+    [cmd @ ("forward" | "down" | "up"), i] => Ok((cmd.to_owned(), parse_at(input, i)?)),
+    [cmd, _] => Err(input_error(input, cmd, "Unknown command")),
+    _ => Err(input_error(input, s, "Expected a command and a distance")),
   }
 }
 
-fn part1(fname: &str) -> i32 {
+pub fn parse(s: &str) -> AocResult<Vec<(String, i32)>> {
+  s.lines().map(|line| do_parse(s, line)).collect()
+}
+
+pub fn part1(commands: &[(String, i32)]) -> AocResult<i32> {
   let mut r = pt!(0, 0);
-  read_or_die(fname)
-    .lines()
-    .map(do_parse)
-    .for_each(|p| match p {
+  commands
+    .iter()
+    .for_each(|(cmd, value)| match (cmd.as_str(), *value) {
       ("forward", value) => r += pt!(value, 0),
       ("down", value) => r += pt!(0, value),
       ("up", value) => r += pt!(0, -value),
       _ => panic!("Unknown command"),
     });
-  r.x * r.y
+  Ok(r.x * r.y)
 }
 
-fn part2(fname: &str) -> i32 {
+pub fn part2(commands: &[(String, i32)]) -> AocResult<i32> {
   let mut pos = pt!(0, 0);
   let mut aim = 0;
-  read_or_die(fname)
-    .lines()
-    .map(do_parse)
-    .for_each(|p| match p {
+  commands
+    .iter()
+    .for_each(|(cmd, value)| match (cmd.as_str(), *value) {
       ("forward", value) => pos += pt!(value, aim * value),
       ("down", value) => aim += value,
       ("up", value) => aim -= value,
       _ => panic!("Invalid command"),
     });
-  pos.x * pos.y
+  Ok(pos.x * pos.y)
+}
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 2;
+  const TITLE: &'static str = "Dive!";
+  type Input = Vec<(String, i32)>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname), part2(fname)))
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 150);
+    assert_eq!(part2(&input).unwrap(), 900);
+  }
 }
@@ -1,8 +1,9 @@
 use std::fmt::Display;
 
-use crate::error::AocResult;
+use crate::error::{input_error, AocError, AocResult};
+use crate::solutions::Day;
 
-fn most_common_in_column(v: &Vec<Vec<i8>>, col_i: usize) -> i8 {
+fn most_common_in_column(v: &[Vec<i8>], col_i: usize) -> i8 {
   let mut counts = [0, 0];
   for e in v {
     counts[e[col_i] as usize] += 1;
@@ -10,56 +11,114 @@ fn most_common_in_column(v: &Vec<Vec<i8>>, col_i: usize) -> i8 {
   (counts[1] >= counts[0]) as i8
 }
 
-fn to_int(v: &[i8]) -> i32 { v.into_iter().fold(0, |accu, x| accu << 1 | (*x as i32)) }
+fn to_int(v: &[i8]) -> i32 { v.iter().fold(0, |accu, x| accu << 1 | (*x as i32)) }
 
-fn o2gen(v: &Vec<Vec<i8>>) -> i32 {
-  let mut w = v.clone();
-  let mut i = 0;
-  while w.len() > 1 {
+/// Filter the numbers column by column, keeping those whose bit matches the
+/// most common one if `most_common`, until one is left.
+fn rating(v: &[Vec<i8>], most_common: bool, name: &str) -> AocResult<i32> {
+  let mut w = v.to_vec();
+  for i in 0..v[0].len() {
+    if w.len() <= 1 {
+      break;
+    }
     let mc = most_common_in_column(&w, i);
-    w = w.into_iter().filter(|x| x[i] == mc).collect();
-    i += 1;
+    w.retain(|x| (x[i] == mc) == most_common);
   }
-  to_int(&w[0])
-}
-
-fn co2scrub(v: &Vec<Vec<i8>>) -> i32 {
-  let mut w = v.clone();
-  let mut i = 0;
-  while w.len() > 1 {
-    let mc = most_common_in_column(&w, i);
-    w = w.into_iter().filter(|x| x[i] != mc).collect();
-    i += 1;
+  match &w[..] {
+    [x] => Ok(to_int(x)),
+    _ => Err(AocError::Custom(format!("No unique {} rating", name))),
   }
-  to_int(&w[0])
 }
 
-fn parse_input(fname: &str) -> Vec<Vec<i8>> {
-  std::fs::read_to_string(fname)
-    .expect("Reading input failed")
-    .trim()
-    .lines()
-    .map(|x| x.chars().map(|x| (x == '1') as i8).collect())
+fn o2gen(v: &[Vec<i8>]) -> AocResult<i32> { rating(v, true, "oxygen generator") }
+
+fn co2scrub(v: &[Vec<i8>]) -> AocResult<i32> { rating(v, false, "CO2 scrubber") }
+
+pub fn parse(s: &str) -> AocResult<Vec<Vec<i8>>> {
+  let lines = s.trim().lines().collect::<Vec<_>>();
+  let width = lines
+    .first()
+    .ok_or_else(|| input_error(s, s, "Empty input"))?
+    .len();
+  lines
+    .into_iter()
+    .map(|line| {
+      if line.len() != width {
+        return Err(input_error(s, line, format!("Expected {} bits", width)));
+      }
+      line
+        .char_indices()
+        .map(|(i, c)| match c {
+          '0' | '1' => Ok((c == '1') as i8),
+          _ => Err(input_error(s, &line[i..i + c.len_utf8()], "Not a bit")),
+        })
+        .collect()
+    })
     .collect()
 }
 
-fn part1(fname: &str) -> i32 {
-  let inp = parse_input(fname);
+pub fn part1(inp: &[Vec<i8>]) -> AocResult<i32> {
   let n = inp[0].len();
   let val = to_int(
     &(0..n)
-      .map(|i| most_common_in_column(&inp, i))
+      .map(|i| most_common_in_column(inp, i))
       .collect::<Vec<i8>>(),
   );
   let val_inv = ((1 << n) - 1) - val;
-  val * val_inv
+  Ok(val * val_inv)
 }
 
-fn part2(fname: &str) -> i32 {
-  let inp = parse_input(fname);
-  o2gen(&inp) * co2scrub(&inp)
+pub fn part2(inp: &[Vec<i8>]) -> AocResult<i32> { Ok(o2gen(inp)? * co2scrub(inp)?) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname), part2(fname)))
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 3;
+  const TITLE: &'static str = "Binary Diagnostic";
+  type Input = Vec<Vec<i8>>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 198);
+    assert_eq!(part2(&input).unwrap(), 230);
+  }
+
+  #[test]
+  fn repeated_numbers() {
+    let input = parse("01\n01\n").unwrap();
+    let err = part2(&input).unwrap_err().to_string();
+    assert!(err.contains("No unique oxygen generator rating"), "{}", err);
+    assert!(co2scrub(&input).is_err());
+    assert_eq!(part2(&parse("01\n").unwrap()).unwrap(), 1);
+  }
 }
@@ -1,10 +1,10 @@
 use std::fmt::Display;
-use std::io::BufRead;
 
-use crate::error::{aoc_error, AocResult};
+use crate::error::{aoc_error, input_error, parse_at, AocResult};
+use crate::solutions::Day;
 
-#[derive(Debug)]
-struct Board {
+#[derive(Debug, Clone)]
+pub struct Board {
   board: Vec<Vec<i32>>,
   width: usize,
   height: usize,
@@ -42,50 +42,55 @@ impl Board {
     let height = board.len();
     let width = board[0].len();
     let checked = vec![vec![false; width]; height];
-    Board {
-      board,
-      height,
-      width,
-      checked,
-    }
+    Board { board, height, width, checked }
   }
 }
 
-fn parse_input(fname: &str) -> AocResult<(Vec<i32>, Vec<Board>)> {
-  let to_int = |x: &str| x.parse::<i32>().expect("Integer parse failed");
+pub fn parse(s: &str) -> AocResult<(Vec<i32>, Vec<Board>)> {
+  let to_int = |x: &str| parse_at::<i32>(s, x);
 
-  let f = std::fs::File::open(fname)?;
-  let mut lines = std::io::BufReader::new(f).lines();
+  let mut lines = s.lines();
   let numbers: Vec<i32> = lines
     .next()
-    .ok_or(aoc_error("No first line"))??
+    .ok_or_else(|| input_error(s, s, "No first line"))?
     .split(',')
     .map(to_int)
-    .collect();
+    .collect::<AocResult<_>>()?;
 
   let mut boards: Vec<Board> = vec![];
-  loop {
-    if !lines.next().is_some() {
-      break;
+  while let Some(separator) = lines.next() {
+    if !separator.trim().is_empty() {
+      return Err(input_error(
+        s,
+        separator,
+        "Expected an empty line between boards",
+      ));
     }
     let v = (&mut lines)
       .take(5)
       .map(|x| {
-        x.expect("Failed to read line")
+        let row = x
           .split_whitespace()
           .map(to_int)
-          .collect()
+          .collect::<AocResult<Vec<_>>>()?;
+        if row.len() != 5 {
+          return Err(input_error(s, x, "Expected a row of 5 numbers"));
+        }
+        Ok(row)
       })
-      .collect();
+      .collect::<AocResult<Vec<_>>>()?;
+    if v.len() != 5 {
+      return Err(input_error(s, &s[s.len()..], "Incomplete board"));
+    }
     boards.push(Board::new(v));
   }
   Ok((numbers, boards))
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let (numbers, mut boards) = parse_input(fname)?;
+pub fn part1((numbers, boards): &(Vec<i32>, Vec<Board>)) -> AocResult<i32> {
+  let mut boards = boards.clone();
 
-  for n in numbers {
+  for &n in numbers {
     for board in &mut boards {
       board.tick(n);
       if board.is_done() {
@@ -96,11 +101,11 @@ fn part1(fname: &str) -> AocResult<i32> {
   Err(aoc_error("No solution found"))
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let (numbers, mut boards) = parse_input(fname)?;
+pub fn part2((numbers, boards): &(Vec<i32>, Vec<Board>)) -> AocResult<i32> {
+  let mut boards = boards.clone();
 
   let mut n_boards_left = boards.len();
-  for n in numbers {
+  for &n in numbers {
     for board in &mut boards {
       board.tick(n);
       if board.is_done() {
@@ -110,11 +115,58 @@ fn part2(fname: &str) -> AocResult<i32> {
         }
       }
     }
-    boards = boards.into_iter().filter(|b| !b.is_done()).collect();
+    boards.retain(|b| !b.is_done());
   }
   Err(aoc_error("No solution found"))
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 4;
+  const TITLE: &'static str = "Giant Squid";
+  type Input = (Vec<i32>, Vec<Board>);
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 4512);
+    assert_eq!(part2(&input).unwrap(), 1924);
+  }
 }
@@ -1,82 +1,127 @@
-use std::fmt;
+use std::error::Error;
 use std::fmt::Display;
 use std::str::FromStr;
 
-use crate::error::AocResult;
+use crate::error::{input_error, parse_at, AocResult};
+use crate::solutions::Day;
+use crate::utils::grid::Grid;
 
-fn parse_input<T>(fname: &str) -> Result<Vec<((T, T), (T, T))>, Box<dyn std::error::Error>>
+pub type Segment<T> = ((T, T), (T, T));
+
+/// Size of the board lines are drawn on.
+const N: usize = 1000;
+
+fn parse_input<T>(s: &str) -> AocResult<Vec<Segment<T>>>
 where
   T: FromStr,
   T: Copy,
-  <T as FromStr>::Err: fmt::Debug,
+  <T as FromStr>::Err: Error + Send + Sync + 'static,
 {
-  let to_int = |x: &str| -> T { x.parse().expect("Integer parse failed") };
-  let parse_pair = |s: &str| -> Vec<T> { s.split(',').map(to_int).collect() };
-  let parse_line = |s: &str| -> ((T, T), (T, T)) {
-    let v: Vec<T> = s.split(" -> ").map(parse_pair).flatten().collect();
+  let parse_line = |line: &str| -> AocResult<Segment<T>> {
+    let v = line
+      .split(" -> ")
+      .flat_map(|pair| pair.split(','))
+      .map(|x| parse_at(s, x))
+      .collect::<AocResult<Vec<T>>>()?;
     match v[..] {
-      [a, b, c, d] => ((a, b), (c, d)),
-      _ => panic!("T_T"),
+      [a, b, c, d] => Ok(((a, b), (c, d))),
+      _ => Err(input_error(s, line, "Expected 'x1,y1 -> x2,y2'")),
     }
   };
 
-  Ok(
-    std::fs::read_to_string(fname)?
-      .trim()
-      .lines()
-      .map(parse_line)
-      .collect(),
-  )
+  s.trim().lines().map(parse_line).collect()
+}
+
+pub fn parse(s: &str) -> AocResult<Vec<Segment<i32>>> {
+  let segments = parse_input(s)?;
+  for (line, &((x1, y1), (x2, y2))) in s.trim().lines().zip(&segments) {
+    if [x1, y1, x2, y2].iter().any(|c| !(0..N as i32).contains(c)) {
+      return Err(input_error(
+        s,
+        line,
+        format!("Coordinates must be in 0..{}", N),
+      ));
+    }
+    if x1 != x2 && y1 != y2 && (x1 - x2).abs() != (y1 - y2).abs() {
+      return Err(input_error(
+        s,
+        line,
+        "Line is neither straight nor diagonal",
+      ));
+    }
+  }
+  Ok(segments)
 }
 
 type IntType = i32;
 
-#[allow(dead_code)]
-struct Board {
-  n_rows: usize,
-  n_cols: usize,
-  data: Vec<IntType>,
+fn draw_line(board: &mut Grid<IntType>, x1: i32, y1: i32, x2: i32, y2: i32) {
+  let dx = (x2 - x1).signum();
+  let dy = (y2 - y1).signum();
+  let dist = (x1 - x2).abs().max((y1 - y2).abs());
+  for i in 0..=dist {
+    let x = x1 + dx * i;
+    let y = y1 + dy * i;
+    board[(x as usize, y as usize)] += 1;
+  }
 }
 
-impl Board {
-  fn new(n_rows: usize, n_cols: usize) -> Self {
-    Self {
-      n_rows,
-      n_cols,
-      data: vec![0; n_cols * n_rows],
-    }
-  }
-  fn inc(&mut self, i: usize, j: usize) { self.data[i * self.n_cols + j] += 1; }
-  fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
-    let dx = (x2 - x1).signum();
-    let dy = (y2 - y1).signum();
-    let dist = (x1 - x2).abs().max((y1 - y2).abs());
-    for i in 0..=dist {
-      let x = x1 + dx * i;
-      let y = y1 + dy * i;
-      self.inc(y as usize, x as usize);
-    }
+fn count_overlaps(coords: &[Segment<i32>], include_line: impl Fn(&Segment<i32>) -> bool) -> usize {
+  let mut board = Grid::new(N, N, 0);
+
+  for &((x1, y1), (x2, y2)) in coords.iter().filter(|s| include_line(s)) {
+    draw_line(&mut board, x1, y1, x2, y2);
   }
+
+  board.iter().filter(|&&x| x > 1).count()
+}
+
+pub fn part1(coords: &[Segment<i32>]) -> AocResult<usize> {
+  Ok(count_overlaps(coords, |&((x1, y1), (x2, y2))| {
+    x1 == x2 || y1 == y2
+  }))
 }
 
-fn solve(fname: &str) -> AocResult<(usize, usize)> {
-  let coords = parse_input::<i32>(fname).expect("Input read failed");
+pub fn part2(coords: &[Segment<i32>]) -> AocResult<usize> { Ok(count_overlaps(coords, |_| true)) }
 
-  const N: usize = 1000;
-  let mut board1 = Board::new(N, N);
-  let mut board2 = Board::new(N, N);
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
 
-  for ((x1, y1), (x2, y2)) in coords {
-    if x1 != x2 && y1 != y2 {
-      board1.draw_line(x1, y1, x2, y2);
-    };
-    board2.draw_line(x1, y1, x2, y2);
-  }
+pub struct Solver;
 
-  Ok((
-    board1.data.iter().filter(|&&x| x > 1).count(),
-    board2.data.iter().filter(|&&x| x > 1).count(),
-  ))
+impl Day for Solver {
+  const DAY: u32 = 5;
+  const TITLE: &'static str = "Hydrothermal Venture";
+  type Input = Vec<Segment<i32>>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> { solve(fname) }
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 5);
+    assert_eq!(part2(&input).unwrap(), 12);
+  }
+}
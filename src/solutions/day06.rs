@@ -1,40 +1,36 @@
 use std::fmt::Display;
-use std::io::BufRead;
-
-use crate::error::{aoc_error, AocResult};
-
-fn parse_input(fname: &str) -> AocResult<Vec<i32>> {
-  let to_int = |x: &str| -> i32 { x.parse().expect("Integer parse failed") };
-
-  let f = std::fs::File::open(fname)?;
-  let mut lines = std::io::BufReader::new(f).lines();
-  Ok(
-    lines
-      .next()
-      .ok_or(aoc_error("No first line"))??
-      .split(',')
-      .map(to_int)
-      .collect(),
-  )
+
+use crate::error::{input_error, parse_at, AocResult};
+use crate::solutions::Day;
+
+pub fn parse(s: &str) -> AocResult<Vec<i32>> {
+  s.lines()
+    .next()
+    .ok_or_else(|| input_error(s, s, "No first line"))?
+    .split(',')
+    .map(|x| match parse_at(s, x)? {
+      timer @ 0..=8 => Ok(timer),
+      _ => Err(input_error(s, x, "Timer must be in 0..=8")),
+    })
+    .collect()
 }
 
-fn part1(fname: &str) -> AocResult<usize> {
-  let mut vec = parse_input(fname)?;
+pub fn part1(init: &[i32]) -> AocResult<usize> {
+  let mut vec = init.to_vec();
   for _ in 0..80 {
     let zeroes = vec.iter().filter(|&&x| x == 0).count();
     vec = vec
       .into_iter()
       .map(|x| if x > 0 { x - 1 } else { 6 })
       .collect();
-    vec.extend(std::iter::repeat(8).take(zeroes));
+    vec.extend(std::iter::repeat_n(8, zeroes));
   }
   Ok(vec.len())
 }
 
-fn part2(fname: &str) -> AocResult<i64> {
+pub fn part2(init: &[i32]) -> AocResult<i64> {
   let mut counts = [0, 0, 0, 0, 0, 0, 0, 0, 0];
-  let init = parse_input(fname)?;
-  for e in init {
+  for &e in init {
     counts[e as usize] += 1;
   }
   for _ in 0..256 {
@@ -52,6 +48,35 @@ fn part2(fname: &str) -> AocResult<i64> {
   Ok(counts.iter().sum::<i64>())
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 6;
+  const TITLE: &'static str = "Lanternfish";
+  type Input = Vec<i32>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+3,4,3,1,2
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 5934);
+    assert_eq!(part2(&input).unwrap(), 26984457539);
+  }
 }
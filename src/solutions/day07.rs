@@ -1,33 +1,28 @@
 use std::fmt::Display;
-use std::io::BufRead;
-
-use crate::error::{aoc_error, AocResult};
-
-fn parse_input(fname: &str) -> AocResult<Vec<i32>> {
-  let to_int = |x: &str| -> i32 { x.parse().expect("Integer parse failed") };
-
-  let f = std::fs::File::open(fname)?;
-  let mut lines = std::io::BufReader::new(f).lines();
-  Ok(
-    lines
-      .next()
-      .ok_or(aoc_error("No first line"))??
-      .split(',')
-      .map(to_int)
-      .collect(),
-  )
+
+use crate::error::{input_error, parse_at, AocResult};
+use crate::solutions::Day;
+
+pub fn parse(s: &str) -> AocResult<Vec<i32>> {
+  s.lines()
+    .next()
+    .filter(|line| !line.is_empty())
+    .ok_or_else(|| input_error(s, s, "No first line"))?
+    .split(',')
+    .map(|x| parse_at(s, x))
+    .collect()
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let mut vec = parse_input(fname).expect("Input reading failed.");
+pub fn part1(positions: &[i32]) -> AocResult<i32> {
+  let mut vec = positions.to_vec();
   vec.sort();
-  let median = vec[vec.len() / 2 as usize];
+  let median = vec[vec.len() / 2];
   let res: i32 = vec.iter().map(|x| (median - x).abs()).sum();
   Ok(res)
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let mut vec = parse_input(fname).expect("Input reading failed.");
+pub fn part2(positions: &[i32]) -> AocResult<i32> {
+  let mut vec = positions.to_vec();
   vec.sort();
   let res = (vec[0]..=vec[vec.len() - 1])
     .map(|i| {
@@ -43,6 +38,35 @@ fn part2(fname: &str) -> AocResult<i32> {
   Ok(res.unwrap())
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 7;
+  const TITLE: &'static str = "The Treachery of Whales";
+  type Input = Vec<i32>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+16,1,2,0,4,2,7,1,2,14
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 37);
+    assert_eq!(part2(&input).unwrap(), 168);
+  }
 }
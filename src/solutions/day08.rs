@@ -1,38 +1,54 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
-use std::str::FromStr;
 
 use itertools::Itertools;
 
-use crate::error::{aoc_error, AocError, AocResult};
+use crate::error::{input_error, AocResult};
+use crate::solutions::Day;
 
-struct Data {
+pub struct Data {
   patterns: Vec<String>,
   digits: Vec<String>,
 }
 
-impl FromStr for Data {
-  type Err = AocError;
-
-  fn from_str(s: &str) -> Result<Self, Self::Err> {
-    let parts: Vec<String> = s.split(" | ").map(String::from).collect();
-    match &parts[..] {
-      [a, b] => Ok(Data {
-        patterns: a.split(" ").map(String::from).collect(),
-        digits: b.split(" ").map(String::from).collect(),
-      }),
-      _ => Err(aoc_error("Parse error")),
+fn parse_line(input: &str, s: &str) -> AocResult<Data> {
+  let parse_words = |part: &str, n: usize| {
+    let words = part.split(' ').collect_vec();
+    if words.len() != n {
+      return Err(input_error(input, part, format!("Expected {} patterns", n)));
     }
+    for word in &words {
+      let unique = word.bytes().all_unique();
+      if word.is_empty() || !unique || !word.bytes().all(|b| (b'a'..=b'g').contains(&b)) {
+        return Err(input_error(input, word, "Invalid segment pattern"));
+      }
+    }
+    Ok(words.into_iter().map(String::from).collect_vec())
+  };
+
+  match s.split(" | ").collect_vec()[..] {
+    [a, b] => {
+      let patterns = parse_words(a, 10)?;
+      // One pattern for each digit, identified by their number of segments.
+      let lengths = patterns.iter().map(|p| p.len()).sorted().collect_vec();
+      if lengths != [2, 3, 4, 5, 5, 5, 6, 6, 6, 7] {
+        return Err(input_error(
+          input,
+          a,
+          "Patterns do not match the ten digits",
+        ));
+      }
+      Ok(Data { patterns, digits: parse_words(b, 4)? })
+    }
+    _ => Err(input_error(input, s, "Expected 'patterns | digits'")),
   }
 }
 
-fn parse_input(fname: &str) -> AocResult<Vec<Data>> {
-  let content = std::fs::read_to_string(fname)?;
-  content.lines().map(|s| s.parse()).collect()
+pub fn parse(s: &str) -> AocResult<Vec<Data>> {
+  s.lines().map(|line| parse_line(s, line)).collect()
 }
 
-fn part1(fname: &str) -> AocResult<usize> {
-  let vec = parse_input(fname)?;
+pub fn part1(vec: &[Data]) -> AocResult<usize> {
   Ok(
     vec
       .iter()
@@ -46,8 +62,7 @@ fn part1(fname: &str) -> AocResult<usize> {
   )
 }
 
-fn part2(fname: &str) -> AocResult<usize> {
-  let input_data = parse_input(fname)?;
+pub fn part2(input_data: &[Data]) -> AocResult<usize> {
   let mut res = 0;
 
   fn intersect(a: &str, b: &str) -> i32 {
@@ -73,7 +88,7 @@ fn part2(fname: &str) -> AocResult<usize> {
 
     res += data
       .digits
-      .into_iter()
+      .iter()
       .map(|e| {
         match e.len() {
           2 => 1,
@@ -81,9 +96,9 @@ fn part2(fname: &str) -> AocResult<usize> {
           3 => 7,
           7 => 8,
           _ => {
-            match intersect(digit(4), &e) {
+            match intersect(digit(4), e) {
               2 => 2,
-              3 => match intersect(digit(1), &e) {
+              3 => match intersect(digit(1), e) {
                 1 => e.len(), // Because funny
                 2 => {
                   if e.len() == 6 {
@@ -106,8 +121,7 @@ fn part2(fname: &str) -> AocResult<usize> {
 }
 
 #[allow(dead_code)]
-fn part2_slow(fname: &str) -> AocResult<usize> {
-  let vec = parse_input(fname)?;
+fn part2_slow(vec: &[Data]) -> AocResult<usize> {
   let thing: HashMap<&str, usize> = HashMap::from([
     ("abcdefg", 8),
     ("bcdef", 5),
@@ -139,7 +153,7 @@ fn part2_slow(fname: &str) -> AocResult<usize> {
       };
 
       let v: HashSet<String> = x.patterns.iter().map(map_string).collect();
-      if thing.keys().all(|e| v.contains(&e.to_string())) {
+      if thing.keys().all(|e| v.contains(*e)) {
         res += x
           .digits
           .iter()
@@ -152,6 +166,44 @@ fn part2_slow(fname: &str) -> AocResult<usize> {
   Ok(res)
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 8;
+  const TITLE: &'static str = "Seven Segment Search";
+  type Input = Vec<Data>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 26);
+    assert_eq!(part2(&input).unwrap(), 61229);
+  }
 }
@@ -1,101 +1,37 @@
 use std::fmt::Display;
 
 use crate::error::AocResult;
+use crate::solutions::Day;
+use crate::utils::grid::{Grid, Pos};
 
-fn parse_input(fname: &str) -> AocResult<Vec<Vec<i8>>> {
-  let content = std::fs::read_to_string(fname)?;
-  content
-    .lines()
-    .map(|s| {
-      s.chars()
-        .map(|c: char| Ok(c.to_digit(10).unwrap() as i8))
-        .collect()
-    })
-    .collect()
-}
+pub fn parse(s: &str) -> AocResult<Grid<u8>> { Grid::parse_digits(s) }
 
-fn basin_locations(width: i32, height: i32, vec: &Vec<Vec<i8>>) -> Vec<(i32, i32)> {
-  let value_at = |x: i32, y: i32| -> i8 {
-    if x < 0 || y < 0 || x >= width || y >= height {
-      100
-    } else {
-      vec[y as usize][x as usize]
-    }
-  };
-  let mut basins: Vec<(i32, i32)> = vec![];
-  for y in 0..height {
-    for x in 0..width {
-      let v = value_at(x, y);
-      if v < value_at(x - 1, y)
-        && v < value_at(x + 1, y)
-        && v < value_at(x, y - 1)
-        && v < value_at(x, y + 1)
-      {
-        basins.push((x, y));
-      }
-    }
-  }
-  basins
+fn basin_locations(heights: &Grid<u8>) -> Vec<Pos> {
+  heights
+    .positions()
+    .filter(|&pos| heights.neighbours4(pos).all(|n| heights[pos] < heights[n]))
+    .collect()
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let vec = parse_input(fname)?;
-  let height: i32 = vec.len() as i32;
-  let width: i32 = vec[0].len() as i32;
-
-  let value_at = |x: i32, y: i32| -> i8 {
-    if x < 0 || y < 0 || x >= width || y >= height {
-      100
-    } else {
-      vec[y as usize][x as usize]
-    }
-  };
-
-  let basins = basin_locations(width, height, &vec);
-
-  let mut res: i32 = 0;
-  for (x, y) in basins {
-    res += (value_at(x, y) as i32) + 1;
-  }
-  Ok(res)
+pub fn part1(heights: &Grid<u8>) -> AocResult<i32> {
+  let basins = basin_locations(heights);
+  Ok(basins.into_iter().map(|pos| heights[pos] as i32 + 1).sum())
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let mut vec = parse_input(fname)?;
-  let height: i32 = vec.len() as i32;
-  let width: i32 = vec[0].len() as i32;
-
-  let value_at = |vec: &Vec<Vec<i8>>, x: i32, y: i32| -> i8 {
-    if x < 0 || y < 0 || x >= width || y >= height {
-      100
-    } else {
-      vec[y as usize][x as usize]
-    }
-  };
-
-  fn set_value(vec: &mut Vec<Vec<i8>>, x: i32, y: i32) -> &mut i8 {
-    &mut vec[y as usize][x as usize]
-  }
-
-  let basins = basin_locations(width, height, &vec);
-
+pub fn part2(heights: &Grid<u8>) -> AocResult<i32> {
+  let mut grid = heights.clone();
   let mut basin_sizes: Vec<i32> = vec![];
 
-  for basin in basins {
-    let mut stack: Vec<(i32, i32)> = vec![basin];
+  for basin in basin_locations(heights) {
+    let mut stack: Vec<Pos> = vec![basin];
     let mut basin_size = 0;
-    while !stack.is_empty() {
-      let (x, y) = stack.pop().unwrap();
-
-      if value_at(&vec, x, y) >= 9 {
+    while let Some(pos) = stack.pop() {
+      if grid[pos] >= 9 {
         continue;
       }
-      *set_value(&mut vec, x, y) = 9;
+      grid[pos] = 9;
       basin_size += 1;
-
-      for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
-        stack.push((x + dx, y + dy))
-      }
+      stack.extend(grid.neighbours4(pos));
     }
     basin_sizes.push(basin_size);
   }
@@ -104,6 +40,39 @@ fn part2(fname: &str) -> AocResult<i32> {
   Ok(basin_sizes.into_iter().take(3).product())
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 9;
+  const TITLE: &'static str = "Smoke Basin";
+  type Input = Grid<u8>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+2199943210
+3987894921
+9856789892
+8767896789
+9899965678
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 15);
+    assert_eq!(part2(&input).unwrap(), 1134);
+  }
 }
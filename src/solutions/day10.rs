@@ -1,18 +1,18 @@
 use std::fmt::Display;
 
-use crate::error::AocResult;
+use crate::error::{aoc_error, input_error, AocResult};
+use crate::solutions::Day;
 
-fn parse_input(fname: &str) -> AocResult<Vec<String>> {
-  let content = std::fs::read_to_string(fname)?;
-  Ok(content.lines().map(|x| x.to_owned()).collect())
+pub fn parse(s: &str) -> AocResult<Vec<String>> {
+  s.lines()
+    .map(|line| match line.find(|c| !"()[]{}<>".contains(c)) {
+      Some(i) => Err(input_error(s, &line[i..], "Not a bracket")),
+      None => Ok(line.to_owned()),
+    })
+    .collect()
 }
 
-fn is_closing(c: char) -> bool {
-  match c {
-    ')' | ']' | '}' | '>' => true,
-    _ => false,
-  }
-}
+fn is_closing(c: char) -> bool { matches!(c, ')' | ']' | '}' | '>') }
 
 fn matching_open(c: char) -> char {
   match c {
@@ -36,23 +36,20 @@ fn error_score(c: char) -> i32 {
   }
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let lines = parse_input(fname)?;
-
+pub fn part1(lines: &[String]) -> AocResult<i32> {
   let res = lines
     .iter()
     .map(|v| {
       // Typical approach stack to track balanced parens
+      // A closing bracket with nothing open is corrupted too.
       let mut stack = vec![];
       for c in v.chars() {
-        if is_closing(c) && !stack.is_empty() {
-          if *stack.last().unwrap() == matching_open(c) {
-            stack.pop();
-          } else {
-            return error_score(c);
-          }
-        } else {
+        if !is_closing(c) {
           stack.push(c);
+        } else if stack.last() == Some(&matching_open(c)) {
+          stack.pop();
+        } else {
+          return error_score(c);
         }
       }
       0
@@ -71,41 +68,98 @@ fn completion_score(c: char) -> i64 {
   }
 }
 
-fn part2(fname: &str) -> AocResult<i64> {
-  let lines = parse_input(fname)?;
-
+pub fn part2(lines: &[String]) -> AocResult<i64> {
   let mut scores: Vec<i64> = lines
     .iter()
     .filter_map(|v| {
       // Typical stack approach to track balanced parens
       let mut stack: Vec<char> = vec![];
       for c in v.chars() {
-        if !is_closing(c) || stack.is_empty() {
+        if !is_closing(c) {
           stack.push(c);
-          continue;
-        }
-        if *stack.last().unwrap() == matching_open(c) {
+        } else if stack.last() == Some(&matching_open(c)) {
           stack.pop();
         } else {
           return None;
         }
       }
+      // Balanced lines need no completion.
+      if stack.is_empty() {
+        return None;
+      }
 
       Some(
         stack
           .into_iter()
           .rev()
-          .map(|x| completion_score(x))
+          .map(completion_score)
           .fold(0, |accu, el| accu * 5 + el),
       )
     })
     .collect();
 
+  if scores.is_empty() {
+    return Err(aoc_error("No incomplete lines"));
+  }
   let middle = scores.len() / 2;
   let (_, median, _) = scores.select_nth_unstable(middle);
   Ok(*median)
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 10;
+  const TITLE: &'static str = "Syntax Scoring";
+  type Input = Vec<String>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 26397);
+    assert_eq!(part2(&input).unwrap(), 288957);
+  }
+
+  #[test]
+  fn no_incomplete_lines() {
+    assert!(part2(&parse("").unwrap()).is_err());
+    let input = parse("{([(<{}[<>[]}>{[]{[(<()>\n[[<[([]))<([[{}[[()]]]\n").unwrap();
+    assert_eq!(part1(&input).unwrap(), 1197 + 3);
+    assert!(part2(&input).is_err());
+    let input = parse(")\n").unwrap();
+    assert_eq!(part1(&input).unwrap(), 3);
+    assert!(part2(&input).is_err());
+    let input = parse("[]\n").unwrap();
+    assert_eq!(part1(&input).unwrap(), 0);
+    assert!(part2(&input).is_err());
+    let input = parse("[]\n<\n").unwrap();
+    assert_eq!(part2(&input).unwrap(), 4);
+  }
 }
@@ -1,90 +1,101 @@
 use std::fmt::Display;
 
-use itertools::Itertools;
-
 use crate::error::AocResult;
+use crate::solutions::Day;
+use crate::utils::grid::Grid;
 
-fn parse_input(fname: &str) -> AocResult<Vec<Vec<i8>>> {
-  let content = std::fs::read_to_string(fname)?;
-  Ok(
-    content
-      .lines()
-      .map(|x| x.chars().map(|e| e.to_digit(10).unwrap() as i8).collect())
-      .collect(),
-  )
-}
+pub fn parse(s: &str) -> AocResult<Grid<i8>> { Ok(Grid::parse_digits(s)?.map(|&d| d as i8)) }
 
-fn sim1(grid: &mut Vec<Vec<i8>>) -> i32 {
-  let h = grid.len() as i32;
-  let w = grid[0].len() as i32;
+fn sim1(grid: &mut Grid<i8>) -> i32 {
   let mut n_flashes = 0;
 
   let mut burst = vec![];
-  for y in 0..h {
-    for x in 0..w {
-      let v = &mut grid[y as usize][x as usize];
-      *v += 1;
-      if *v > 9 {
-        burst.push((y, x));
-        *v = -100;
-      }
+  for pos in grid.positions() {
+    let v = &mut grid[pos];
+    *v += 1;
+    if *v > 9 {
+      burst.push(pos);
+      *v = -100;
     }
   }
 
-  while !burst.is_empty() {
-    let (y, x) = burst.pop().unwrap();
+  while let Some(pos) = burst.pop() {
     n_flashes += 1;
 
-    let neighbors = (-1..=1)
-      .map(|i| (-1..=1).map(|j| (i, j)).collect_vec())
-      .flatten()
-      .filter_map(|(i, j)| {
-        if i == 0 && j == 0 {
-          None
-        } else {
-          Some((y + i, x + j))
-        }
-      })
-      .filter(|&(i, j)| 0 <= i && i < h && 0 <= j && j < w)
-      .collect_vec();
-
-    for (ny, nx) in neighbors {
-      let v = &mut grid[ny as usize][nx as usize];
+    let neighbors = grid.neighbours8(pos).collect::<Vec<_>>();
+    for n in neighbors {
+      let v = &mut grid[n];
       *v += 1;
       if *v > 9 {
-        burst.push((ny, nx));
+        burst.push(n);
         *v = -100;
       }
     }
   }
 
-  for y in 0..h {
-    for x in 0..w {
-      let v = &mut grid[y as usize][x as usize];
-      *v = (*v).max(0);
-    }
+  for v in grid.iter_mut() {
+    *v = (*v).max(0);
   }
 
   n_flashes
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let mut grid = parse_input(fname)?;
+pub fn part1(grid: &Grid<i8>) -> AocResult<i32> {
+  let mut grid = grid.clone();
   Ok((0..100).map(|_| sim1(&mut grid)).sum())
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let mut grid = parse_input(fname)?;
+pub fn part2(grid: &Grid<i8>) -> AocResult<i32> {
+  let mut grid = grid.clone();
+  let size = (grid.width() * grid.height()) as i32;
   let mut step = 0;
   loop {
     step += 1;
-    if sim1(&mut grid) == 100 {
+    if sim1(&mut grid) == size {
       break;
     }
   }
   Ok(step)
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 11;
+  const TITLE: &'static str = "Dumbo Octopus";
+  type Input = Grid<i8>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 1656);
+    assert_eq!(part2(&input).unwrap(), 195);
+  }
 }
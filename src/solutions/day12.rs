@@ -1,21 +1,19 @@
 use std::collections::HashMap;
 use std::fmt::Display;
-use std::iter::repeat;
 
 use itertools::Itertools;
 
-use crate::error::AocResult;
+use crate::error::{input_error, AocResult};
+use crate::solutions::Day;
 
-struct Graph {
+pub struct Graph {
   conn: Vec<Vec<usize>>,
   start: usize,
   end: usize,
   is_big: Vec<bool>,
 }
 
-fn parse_input(fname: &str) -> AocResult<Graph> {
-  let content = std::fs::read_to_string(fname)?;
-
+pub fn parse(content: &str) -> AocResult<Graph> {
   let mut index_map = HashMap::new();
 
   let mut get_index = |s: &str| {
@@ -23,15 +21,11 @@ fn parse_input(fname: &str) -> AocResult<Graph> {
     *index_map.entry(s.to_owned()).or_insert(sz)
   };
 
-  let mut g = Graph {
-    conn: vec![],
-    is_big: vec![],
-    start: 0,
-    end: 0,
-  };
+  let mut g = Graph { conn: vec![], is_big: vec![], start: 0, end: 0 };
 
-  for parts in content.lines().map(|x| x.split('-').collect_vec()) {
-    match parts[..] {
+  let (mut has_start, mut has_end) = (false, false);
+  for line in content.lines() {
+    match line.split('-').collect_vec()[..] {
       [astr, bstr] => {
         let a = get_index(astr);
         let b = get_index(bstr);
@@ -58,21 +52,28 @@ fn parse_input(fname: &str) -> AocResult<Graph> {
         g.conn[a].push(b);
         g.conn[b].push(a);
         if astr == "start" {
-          g.start = a
+          g.start = a;
+          has_start = true;
         }
         if astr == "end" {
-          g.end = a
+          g.end = a;
+          has_end = true;
         }
         if bstr == "start" {
-          g.start = b
+          g.start = b;
+          has_start = true;
         }
         if bstr == "end" {
-          g.end = b
+          g.end = b;
+          has_end = true;
         }
       }
-      _ => panic!("Weird input"),
+      _ => return Err(input_error(content, line, "Expected 'cave-cave'")),
     };
   }
+  if !has_start || !has_end {
+    return Err(input_error(content, content, "Missing start or end cave"));
+  }
 
   Ok(g)
 }
@@ -95,10 +96,9 @@ fn dfs(g: &Graph, visited: &mut Vec<bool>, cur: usize, target: usize) -> i32 {
   count
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let g = parse_input(fname)?;
-  let mut visited = repeat(false).take(g.conn.len()).collect_vec();
-  Ok(dfs(&g, &mut visited, g.start, g.end))
+pub fn part1(g: &Graph) -> AocResult<i32> {
+  let mut visited = std::iter::repeat_n(false, g.conn.len()).collect_vec();
+  Ok(dfs(g, &mut visited, g.start, g.end))
 }
 
 fn dfs2(
@@ -136,12 +136,46 @@ fn dfs2(
   count
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let g = parse_input(fname)?;
-  let mut visited = repeat(false).take(g.conn.len()).collect_vec();
-  Ok(dfs2(&g, &mut visited, g.start, g.start, g.end, &mut -1))
+pub fn part2(g: &Graph) -> AocResult<i32> {
+  let mut visited = std::iter::repeat_n(false, g.conn.len()).collect_vec();
+  Ok(dfs2(g, &mut visited, g.start, g.start, g.end, &mut -1))
+}
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 12;
+  const TITLE: &'static str = "Passage Pathing";
+  type Input = Graph;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+start-A
+start-b
+A-c
+A-b
+b-d
+A-end
+b-end
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 10);
+    assert_eq!(part2(&input).unwrap(), 36);
+  }
 }
@@ -2,73 +2,79 @@ use std::fmt::Display;
 
 use itertools::Itertools;
 
-use crate::error::{aoc_error, AocResult};
+use crate::error::{aoc_error, input_error, parse_at, AocResult};
+use crate::solutions::Day;
 
-fn parse_input(fname: &str) -> AocResult<(Vec<(i32, i32)>, Vec<(char, i32)>)> {
-  let content = std::fs::read_to_string(fname)?;
+type Point = (i32, i32);
+type Fold = (char, i32);
+
+pub fn parse(content: &str) -> AocResult<(Vec<Point>, Vec<Fold>)> {
   let sections = content.split("\n\n").collect_vec();
 
   if sections.len() != 2 {
-    return Err(aoc_error("Wrong number of sections"));
+    return Err(input_error(
+      content,
+      content,
+      "Expected dots and folds separated by an empty line",
+    ));
   }
 
-  let points: Vec<(i32, i32)> = sections[0]
+  let points: Vec<Point> = sections[0]
     .lines()
-    .map(|s| {
-      let parts = s
-        .split(",")
-        .map(|x| x.parse::<i32>().unwrap())
-        .collect_vec();
-      (parts[0], parts[1])
+    .map(|s| match s.split(',').collect_vec()[..] {
+      [x, y] => Ok((parse_at(content, x)?, parse_at(content, y)?)),
+      _ => Err(input_error(content, s, "Expected 'x,y'")),
     })
-    .collect_vec();
+    .collect::<AocResult<_>>()?;
 
-  let fold: Vec<(char, i32)> = sections[1]
+  let fold: Vec<Fold> = sections[1]
     .lines()
     .map(|s| {
-      let parts = s.split(" ").last().unwrap().split("=").collect_vec();
-      (
-        parts[0].chars().next().unwrap(),
-        parts[1].parse::<i32>().unwrap(),
-      )
+      let instruction = s
+        .strip_prefix("fold along ")
+        .ok_or_else(|| input_error(content, s, "Expected 'fold along axis=value'"))?;
+      match instruction.split_once('=') {
+        Some(("x", value)) => Ok(('x', parse_at(content, value)?)),
+        Some(("y", value)) => Ok(('y', parse_at(content, value)?)),
+        _ => Err(input_error(
+          content,
+          instruction,
+          "Expected 'x=value' or 'y=value'",
+        )),
+      }
     })
-    .collect_vec();
+    .collect::<AocResult<_>>()?;
 
   Ok((points, fold))
 }
 
-fn solve(fname: &str) -> AocResult<(usize, String)> {
-  let (mut points, instructions) = parse_input(fname)?;
-
-  let mut res1 = None;
+fn fold(points: Vec<Point>, inst: Fold) -> Vec<Point> {
+  match inst {
+    ('x', val) => points
+      .into_iter()
+      .map(|(x, y)| (if x <= val { x } else { val - (x - val) }, y))
+      .unique()
+      .collect_vec(),
+    ('y', val) => points
+      .into_iter()
+      .map(|(x, y)| (x, if y <= val { y } else { val - (y - val) }))
+      .unique()
+      .collect_vec(),
+    _ => panic!("AAA"),
+  }
+}
 
-  let mut first = true;
-  for inst in instructions {
-    match inst {
-      ('x', val) => {
-        points = points
-          .into_iter()
-          .map(|(x, y)| (if x <= val { x } else { val - (x - val) }, y))
-          .unique()
-          .collect_vec()
-      }
-      ('y', val) => {
-        points = points
-          .into_iter()
-          .map(|(x, y)| (x, if y <= val { y } else { val - (y - val) }))
-          .unique()
-          .collect_vec()
-      }
-      _ => panic!("AAA"),
-    }
+pub fn part1((points, instructions): &(Vec<Point>, Vec<Fold>)) -> AocResult<usize> {
+  let first = instructions.first().ok_or(aoc_error("No folds"))?;
+  Ok(fold(points.clone(), *first).len())
+}
 
-    if first {
-      res1 = Some(points.len());
-      first = false;
-    }
-  }
+pub fn part2((points, instructions): &(Vec<Point>, Vec<Fold>)) -> AocResult<String> {
+  let points = instructions
+    .iter()
+    .fold(points.clone(), |points, &inst| fold(points, inst));
 
-  let res2 = "\n".to_owned()
+  let res = "\n".to_owned()
     + &(0..7)
       .map(|y| {
         (0..40)
@@ -77,7 +83,64 @@ fn solve(fname: &str) -> AocResult<(usize, String)> {
       })
       .join("\n");
 
-  Ok((res1.unwrap(), res2))
+  Ok(res)
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> { solve(fname) }
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 13;
+  const TITLE: &'static str = "Transparent Origami";
+  type Input = (Vec<Point>, Vec<Fold>);
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+6,10
+0,14
+9,10
+0,3
+10,4
+4,11
+6,0
+6,12
+4,1
+0,13
+10,12
+3,4
+3,0
+8,4
+1,10
+2,14
+8,10
+9,0
+
+fold along y=7
+fold along x=5
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 17);
+    let code = part2(&input).unwrap();
+    let lines = code
+      .trim()
+      .lines()
+      .map(|l| l.trim_end())
+      .collect::<Vec<_>>();
+    assert_eq!(lines, ["#####", "#   #", "#   #", "#   #", "#####"]);
+  }
+}
@@ -3,32 +3,44 @@ use std::fmt::Display;
 
 use itertools::Itertools;
 
-use crate::error::{aoc_error, AocResult};
+use crate::error::{input_error, AocResult};
+use crate::solutions::Day;
 
-fn parse_input(fname: &str) -> AocResult<(String, Vec<(String, String)>)> {
-  let content = std::fs::read_to_string(fname)?;
+type Rule = (String, String);
+
+pub fn parse(content: &str) -> AocResult<(String, Vec<Rule>)> {
   let sections = content.split("\n\n").collect_vec();
 
   if sections.len() != 2 {
-    return Err(aoc_error("Wrong number of sections"));
+    return Err(input_error(
+      content,
+      content,
+      "Expected a template and rules separated by an empty line",
+    ));
   }
 
-  let template = sections[0].to_owned();
+  let is_element = |s: &str| s.bytes().all(|b| b.is_ascii_uppercase());
+  let template = sections[0];
+  if template.is_empty() || !is_element(template) {
+    return Err(input_error(content, template, "Invalid polymer template"));
+  }
   let rules = sections[1]
     .lines()
-    .map(|s| {
-      let parts = s.split(" -> ").collect_vec();
-      (parts[0].to_owned(), parts[1].to_owned())
+    .map(|s| match s.split(" -> ").collect_vec()[..] {
+      [pair, insert]
+        if pair.len() == 2 && insert.len() == 1 && is_element(pair) && is_element(insert) =>
+      {
+        Ok((pair.to_owned(), insert.to_owned()))
+      }
+      _ => Err(input_error(content, s, "Expected a rule like 'AB -> C'")),
     })
-    .collect();
+    .collect::<AocResult<_>>()?;
 
-  Ok((template, rules))
+  Ok((template.to_owned(), rules))
 }
 
-fn solve(fname: &str, steps: i32) -> AocResult<i64> {
-  let (template, rules) = parse_input(fname)?;
-
-  let x = "$".to_owned() + &template + "$";
+fn solve((template, rules): &(String, Vec<Rule>), steps: i32) -> AocResult<i64> {
+  let x = "$".to_owned() + template + "$";
   let mut counts = HashMap::new();
   (0..x.len() - 1).map(|i| &x[i..i + 2]).for_each(|s| {
     *counts.entry(s.to_owned()).or_insert(0) += 1;
@@ -36,7 +48,7 @@ fn solve(fname: &str, steps: i32) -> AocResult<i64> {
 
   for _ in 0..steps {
     let mut new_counts = counts.clone();
-    for (a, b) in &rules {
+    for (a, b) in rules {
       let first = a[0..1].to_owned() + b;
       let second = b.to_owned() + &a[1..2];
       let count = counts.get(a.as_str()).unwrap_or(&0);
@@ -54,18 +66,60 @@ fn solve(fname: &str, steps: i32) -> AocResult<i64> {
     }
   }
 
-  let freqs: Vec<i64> = final_counts
-    .iter()
-    .map(|(_, v)| v / 2)
-    .sorted()
-    .collect_vec();
+  let freqs: Vec<i64> = final_counts.values().map(|v| v / 2).sorted().collect_vec();
   Ok(freqs[freqs.len() - 1] - freqs[1])
 }
 
-fn part1(fname: &str) -> AocResult<i64> { solve(fname, 10) }
+pub fn part1(input: &(String, Vec<Rule>)) -> AocResult<i64> { solve(input, 10) }
+
+pub fn part2(input: &(String, Vec<Rule>)) -> AocResult<i64> { solve(input, 40) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
 
-fn part2(fname: &str) -> AocResult<i64> { solve(fname, 40) }
+impl Day for Solver {
+  const DAY: u32 = 14;
+  const TITLE: &'static str = "Extended Polymerization";
+  type Input = (String, Vec<Rule>);
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 1588);
+    assert_eq!(part2(&input).unwrap(), 2188189693529);
+  }
 }
@@ -1,138 +1,148 @@
 use std::fmt::Display;
-use std::iter::repeat;
 
-use itertools::Itertools;
+use crate::error::{aoc_error, AocResult};
+use crate::solutions::Day;
+use crate::utils::grid::{Grid, Pos};
+use crate::utils::search::{self, Cost, SearchProblem};
 
-use crate::error::AocResult;
+pub fn parse(s: &str) -> AocResult<Grid<u8>> {
+  Grid::parse_chars(s, "a risk level from 1 to 9", |c| {
+    c.to_digit(10).filter(|&d| d > 0).map(|d| d as u8)
+  })
+}
 
-fn parse_input(fname: &str) -> AocResult<Vec<Vec<u8>>> {
-  let grid = std::fs::read_to_string(fname)?
-    .lines()
-    .map(|s| s.bytes().map(|b| b - ('0' as u8)).collect_vec())
-    .collect_vec();
-  Ok(grid)
+/// Walking from the top left to the bottom right of the risk map.
+struct Chiton<'a> {
+  grid: &'a Grid<u8>,
 }
 
-struct State {
-  x: i32,
-  y: i32,
+impl SearchProblem for Chiton<'_> {
+  type State = Pos;
+  fn successors(&self, &pos: &Pos) -> impl Iterator<Item = (Pos, Cost)> {
+    self
+      .grid
+      .neighbours4(pos)
+      .map(|next| (next, self.grid[next] as Cost))
+  }
+  fn is_goal(&self, &pos: &Pos) -> bool { pos == (self.grid.width() - 1, self.grid.height() - 1) }
 }
 
-struct CyclicPQ {
-  distance: usize,
-  buckets: [Vec<State>; 16],
+/// The total risk and the cells of a safest route from the top left to the
+/// bottom right, both ends included.
+pub fn route(grid: &Grid<u8>) -> AocResult<(i32, Vec<Pos>)> {
+  // Risks are 1-9, so a bucket queue beats a heap.
+  let path = search::dial(&Chiton { grid }, (0, 0), 9).ok_or(aoc_error("No path"))?;
+  Ok((path.cost as i32, path.states))
 }
 
-impl CyclicPQ {
-  fn new() -> CyclicPQ {
-    CyclicPQ {
-      distance: 0,
-      buckets: [
-        vec![State { x: 0, y: 0 }],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-        vec![],
-      ],
-    }
+/// The risk map with only the risks along `cells` shown.
+pub fn render_route(grid: &Grid<u8>, cells: &[Pos]) -> String {
+  let mut map = grid.map(|_| '.');
+  for &pos in cells {
+    map[pos] = char::from(b'0' + grid[pos]);
   }
+  map.to_string()
+}
 
-  fn push(&mut self, s: State, dist: usize) {
-    let n = self.buckets.len();
-    self.buckets[dist % n].push(s);
-  }
+fn shortest_path(grid: &Grid<u8>) -> AocResult<i32> { Ok(route(grid)?.0) }
 
-  fn pop(&mut self) -> Option<State> {
-    while self.buckets[self.distance % self.buckets.len()].is_empty() {
-      self.distance += 1;
-    }
-    self.buckets[self.distance % self.buckets.len()].pop()
-  }
+pub fn part1(grid: &Grid<u8>) -> AocResult<i32> { shortest_path(grid) }
+
+pub fn part2(grid: &Grid<u8>) -> AocResult<i32> {
+  let n = 5;
+  let (width, height) = (grid.width(), grid.height());
+  let expanded = Grid::from_fn(n * width, n * height, |(x, y)| {
+    let tile = (x / width + y / height) as u8;
+    (grid[(x % width, y % height)] + tile - 1) % 9 + 1
+  });
+  shortest_path(&expanded)
 }
 
-const INFTY: usize = 1000000000;
-
-fn shortest_path(grid: Vec<Vec<u8>>) -> AocResult<i32> {
-  let height = grid.len() as i32;
-  let width = grid[0].len() as i32;
-
-  let mut dist_to = (0..height)
-    .map(|_| repeat(INFTY).take(width as usize).collect_vec())
-    .collect_vec();
-
-  // Silly optimization, makes use of 1-9 costs.
-  let mut pq = CyclicPQ::new();
-
-  while let Some(cur) = pq.pop() {
-    if cur.x == width - 1 && cur.y == height - 1 {
-      break;
-    }
-
-    for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
-      let x = cur.x + dx;
-      let y = cur.y + dy;
-      if x < 0 || x >= width || y < 0 || y >= height {
-        continue;
-      }
-
-      let new_dist = pq.distance + (grid[y as usize][x as usize] as usize);
-      if new_dist < dist_to[y as usize][x as usize] {
-        dist_to[y as usize][x as usize] = new_dist;
-        pq.push(State { x, y }, new_dist);
-      }
-    }
-  }
-  Ok(pq.distance as i32)
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let grid = parse_input(fname)?;
-  shortest_path(grid)
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 15;
+  const TITLE: &'static str = "Chiton";
+  type Input = Grid<u8>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+  fn trace(input: &Self::Input) -> AocResult<Option<String>> {
+    let (risk, cells) = route(input)?;
+    Ok(Some(format!(
+      "Safest route of part 1, risk {}:\n{}",
+      risk,
+      render_route(input, &cells)
+    )))
+  }
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let grid = parse_input(fname)?;
-  let n = 5;
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 40);
+    assert_eq!(part2(&input).unwrap(), 315);
+  }
 
-  // lmao
-  let expanded = (0..n)
-    .flat_map(|i| {
-      grid
-        .iter()
-        .map(|row| {
-          (0..n)
-            .flat_map(|j| row.iter().map(move |val| (val + j + i - 1) % 9 + 1))
-            .collect_vec()
-        })
-        .collect_vec()
-    })
-    .collect_vec();
-
-  //let mut expanded = vec![];
-  //for i in 0..n {
-  //  for row in grid.iter() {
-  //    let mut new_row = vec![];
-  //    for j in 0..n {
-  //      new_row.extend(row.iter().map(|val| (val + j + i - 1) % 9 + 1));
-  //    }
-  //    expanded.push(new_row);
-  //  }
-  //}
-
-  shortest_path(expanded)
-}
+  #[test]
+  fn zero_risk() {
+    let err = parse("19\n90\n").unwrap_err().to_string();
+    assert!(
+      err.starts_with("line 2, column 2: Expected a risk level from 1 to 9 '0'"),
+      "{}",
+      err
+    );
+  }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+  #[test]
+  fn sample_route() {
+    let input = parse(SAMPLE).unwrap();
+    let (risk, cells) = route(&input).unwrap();
+    assert_eq!(risk, 40);
+    assert_eq!(
+      cells
+        .iter()
+        .skip(1)
+        .map(|&pos| input[pos] as i32)
+        .sum::<i32>(),
+      40
+    );
+    assert_eq!(
+      render_route(&input, &cells),
+      "\
+1.........
+1.........
+2136511...
+......15..
+.......1..
+.......13.
+........2.
+........3.
+........21
+.........1"
+    );
+  }
 }
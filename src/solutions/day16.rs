@@ -2,29 +2,37 @@ use std::fmt::Display;
 
 use itertools::Itertools;
 
-use crate::error::{AocError, AocResult};
+use crate::error::{aoc_error, input_error, parse_at, AocError, AocResult};
+use crate::solutions::Day;
 
 type ArithInt = i64;
 type ParseInt = i64;
 
+/// A decoding error and the bit offset where it happened.
+type DecodeError = (usize, String);
+
 // Bitstream impl
 struct BitStream {
   i: usize,
-  data: Vec<char>,
+  /// One hex digit per element.
+  data: Vec<u8>,
 }
 
 impl BitStream {
-  fn new(hex_string: String) -> BitStream {
-    BitStream {
-      i: 0,
-      data: hex_string.chars().collect_vec(),
-    }
-  }
+  fn new(digits: Vec<u8>) -> BitStream { BitStream { i: 0, data: digits } }
   fn cur_pos(&self) -> usize { self.i }
-  fn read_int(&mut self, n: usize) -> ParseInt {
-    self
-      .take(n)
-      .fold(0, |accu, el| (accu as ParseInt) << 1 | (el as ParseInt))
+  fn len(&self) -> usize { self.data.len() * 4 }
+  fn read_int(&mut self, n: usize) -> Result<ParseInt, DecodeError> {
+    if self.i + n > self.len() {
+      let left = self.len() - self.i;
+      let msg = format!("Expected {} more bits, but only {} are left", n, left);
+      return Err((self.i, msg));
+    }
+    Ok(
+      self
+        .take(n)
+        .fold(0, |accu, el| (accu as ParseInt) << 1 | (el as ParseInt)),
+    )
   }
 }
 
@@ -32,18 +40,17 @@ impl Iterator for BitStream {
   type Item = bool;
   fn next(&mut self) -> Option<Self::Item> {
     let (i, offset) = (self.i / 4, self.i % 4);
-    let digit = self.data.get(i)?.to_digit(16)?;
+    let digit = *self.data.get(i)?;
     self.i += 1;
-    return Some(((digit >> (3 - offset)) & 1) == 1);
+    Some(((digit >> (3 - offset)) & 1) == 1)
   }
 }
 
 // AST node
 
-#[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
-enum NodeType {
+pub enum NodeType {
   Sum = 0,
   Prod = 1,
   Min = 2,
@@ -55,17 +62,49 @@ enum NodeType {
 }
 
 impl NodeType {
-  fn from_u8(n: u8) -> NodeType {
-    // This is a bad idea
-    unsafe { std::mem::transmute(n) }
+  fn from_u8(n: u8) -> Option<NodeType> {
+    Some(match n {
+      0 => NodeType::Sum,
+      1 => NodeType::Prod,
+      2 => NodeType::Min,
+      3 => NodeType::Max,
+      4 => NodeType::Literal,
+      5 => NodeType::Greater,
+      6 => NodeType::Less,
+      7 => NodeType::Equal,
+      _ => return None,
+    })
+  }
+
+  /// Check that an operator of this type can take `n` operands.
+  fn check_arity(self, n: usize) -> Result<(), String> {
+    let ok = match self {
+      NodeType::Sum | NodeType::Prod => true,
+      NodeType::Min | NodeType::Max => n >= 1,
+      NodeType::Greater | NodeType::Less | NodeType::Equal => n == 2,
+      NodeType::Literal => false,
+    };
+    if ok {
+      return Ok(());
+    }
+    let expected = match self {
+      NodeType::Min | NodeType::Max => "at least 1",
+      NodeType::Literal => "none",
+      _ => "2",
+    };
+    Err(format!(
+      "Operator {} takes {} operands, not {}",
+      self.name(),
+      expected,
+      n
+    ))
   }
 }
 
-#[derive(Debug)]
-enum Node {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
   Literal {
     version: u8,
-    #[allow(dead_code)]
     node_type: NodeType,
     value: ArithInt,
   },
@@ -76,115 +115,622 @@ enum Node {
   },
 }
 
-fn parse(bs: &mut BitStream) -> Node {
-  fn read_varint(bs: &mut BitStream) -> ArithInt {
+impl Node {
+  pub fn literal(version: u8, value: ArithInt) -> Node {
+    Node::Literal { version, node_type: NodeType::Literal, value }
+  }
+
+  pub fn operator(version: u8, node_type: NodeType, subpackages: Vec<Node>) -> Node {
+    Node::Operator { version, node_type, subpackages }
+  }
+}
+
+fn parse_packet(bs: &mut BitStream) -> Result<Node, DecodeError> {
+  fn read_varint(bs: &mut BitStream) -> Result<ArithInt, DecodeError> {
     // 1+4 bit int encoding
-    let mut res = 0;
+    let start = bs.cur_pos();
+    let mut res: ArithInt = 0;
     loop {
-      let x = bs.read_int(5);
+      let x = bs.read_int(5)?;
+      if res >> 59 != 0 {
+        return Err((start, "Literal does not fit 63 bits".to_owned()));
+      }
       res = res << 4 | (x & 0b1111);
       if x & 0b10000 == 0 {
         break;
       }
     }
-    res
+    Ok(res)
   }
 
-  fn subpkg_by_len(mut bs: &mut BitStream) -> Vec<Node> {
-    let bit_len = bs.read_int(15) as usize;
+  fn subpkg_by_len(bs: &mut BitStream) -> Result<Vec<Node>, DecodeError> {
+    let bit_len = bs.read_int(15)? as usize;
     let start = bs.cur_pos();
+    if start + bit_len > bs.len() {
+      let msg = format!(
+        "Subpackets of {} bits, but only {} bits are left",
+        bit_len,
+        bs.len() - start
+      );
+      return Err((start, msg));
+    }
     let mut subpackages = vec![];
     while bs.cur_pos() < start + bit_len {
-      subpackages.push(parse(&mut bs));
+      subpackages.push(parse_packet(bs)?);
     }
-    subpackages
+    if bs.cur_pos() != start + bit_len {
+      let msg = format!(
+        "Subpackets take {} bits, expected {}",
+        bs.cur_pos() - start,
+        bit_len
+      );
+      return Err((start, msg));
+    }
+    Ok(subpackages)
   }
 
-  fn subpkg_by_count(mut bs: &mut BitStream) -> Vec<Node> {
-    let n_sub_packages = bs.read_int(11);
-    (0..n_sub_packages).map(|_| parse(&mut bs)).collect_vec()
+  fn subpkg_by_count(bs: &mut BitStream) -> Result<Vec<Node>, DecodeError> {
+    let n_sub_packages = bs.read_int(11)?;
+    (0..n_sub_packages).map(|_| parse_packet(bs)).collect()
   }
 
-  let version = bs.read_int(3) as u8;
-  let node_type = NodeType::from_u8(bs.read_int(3) as u8);
+  let start = bs.cur_pos();
+  let version = bs.read_int(3)? as u8;
+  let type_id = bs.read_int(3)? as u8;
+  let node_type = NodeType::from_u8(type_id)
+    .ok_or_else(|| (start + 3, format!("Invalid type id {}", type_id)))?;
   match node_type {
-    NodeType::Literal => Node::Literal {
-      version,
-      node_type,
-      value: read_varint(bs),
-    },
+    NodeType::Literal => Ok(Node::Literal { version, node_type, value: read_varint(bs)? }),
     _ => {
-      let subpackages: Vec<Node> = match bs.read_int(1) {
-        0 => subpkg_by_len(bs),
-        1 => subpkg_by_count(bs),
-        _ => panic!("impossible"),
+      let subpackages: Vec<Node> = match bs.read_int(1)? {
+        0 => subpkg_by_len(bs)?,
+        _ => subpkg_by_count(bs)?,
       };
+      node_type
+        .check_arity(subpackages.len())
+        .map_err(|msg| (start, msg))?;
+      Ok(Node::Operator { version, node_type, subpackages })
+    }
+  }
+}
 
-      Node::Operator {
-        version,
-        node_type,
-        subpackages,
+/// Decode the BITS transmission `hex`, a part of `input`, pointing errors at
+/// the hex digit where they happened.
+fn decode_in(input: &str, hex: &str, strict_padding: bool) -> AocResult<Node> {
+  if hex.is_empty() {
+    return Err(input_error(input, hex, "Empty transmission"));
+  }
+  if let Some(i) = hex.find(|c: char| !c.is_ascii_hexdigit()) {
+    return Err(input_error(input, &hex[i..], "Not a hexadecimal digit"));
+  }
+  let digits = hex.chars().map(|c| c.to_digit(16).unwrap() as u8).collect();
+  let mut bs = BitStream::new(digits);
+  let mut node = parse_packet(&mut bs);
+  if strict_padding {
+    let end = bs.cur_pos();
+    node = node.and_then(|node| match bs.position(|bit| bit) {
+      Some(i) => Err((end + i, "Padding has a bit set".to_owned())),
+      None => Ok(node),
+    });
+  }
+  node.map_err(|(bit, msg)| {
+    let digit = (bit / 4).min(hex.len() - 1);
+    input_error(
+      input,
+      &hex[digit..digit + 1],
+      format!("bit {}: {}", bit, msg),
+    )
+  })
+}
+
+/// Decode a BITS transmission, rejecting set bits in the padding after the
+/// outermost packet if `strict_padding`.
+pub fn decode(hex: &str, strict_padding: bool) -> AocResult<Node> {
+  decode_in(hex, hex, strict_padding)
+}
+
+pub fn parse(s: &str) -> AocResult<Node> {
+  let first_line = s.lines().next().unwrap_or(s);
+  decode_in(s, first_line, false)
+}
+
+// Encoder
+
+/// How an operator packet gives the extent of its subpackets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthType {
+  /// Length type 0, the total number of bits in 15 bits.
+  Bits,
+  /// Length type 1, the number of subpackets in 11 bits.
+  Count,
+}
+
+#[derive(Default)]
+struct BitWriter {
+  bits: Vec<bool>,
+}
+
+impl BitWriter {
+  fn write_int(&mut self, value: u64, n: usize) {
+    self
+      .bits
+      .extend((0..n).rev().map(|i| (value >> i) & 1 == 1));
+  }
+
+  /// The bits as hex digits, padded with zeros to whole bytes as in the
+  /// puzzle's transmissions.
+  fn to_hex(&self) -> String {
+    let mut bits = self.bits.clone();
+    bits.resize(bits.len().div_ceil(8) * 8, false);
+    bits
+      .chunks(4)
+      .map(|chunk| {
+        let digit = chunk.iter().fold(0, |accu, &bit| accu << 1 | bit as u32);
+        char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+      })
+      .collect()
+  }
+}
+
+fn encode_packet(node: &Node, prefer: LengthType, bw: &mut BitWriter) -> AocResult<()> {
+  fn header(bw: &mut BitWriter, version: u8, node_type: NodeType) -> AocResult<()> {
+    if version > 7 {
+      return Err(AocError::Custom(format!(
+        "Version {} does not fit 3 bits",
+        version
+      )));
+    }
+    bw.write_int(version as u64, 3);
+    bw.write_int(node_type as u64, 3);
+    Ok(())
+  }
+
+  match *node {
+    Node::Literal { version, node_type, value } => {
+      if node_type != NodeType::Literal {
+        return Err(AocError::Custom(format!("Literal of type {:?}", node_type)));
+      }
+      if value < 0 {
+        return Err(AocError::Custom(format!("Negative literal {}", value)));
+      }
+      header(bw, version, node_type)?;
+      // 1+4 bit int encoding, most significant group first
+      let groups = (1..).find(|&n| n >= 16 || value >> (4 * n) == 0).unwrap();
+      for i in (0..groups).rev() {
+        bw.write_int((i > 0) as u64, 1);
+        bw.write_int((value >> (4 * i)) as u64 & 0b1111, 4);
+      }
+    }
+    Node::Operator { version, node_type, ref subpackages } => {
+      if node_type == NodeType::Literal {
+        return Err(aoc_error("Operator of literal type"));
+      }
+      header(bw, version, node_type)?;
+      let mut sub = BitWriter::default();
+      for node in subpackages {
+        encode_packet(node, prefer, &mut sub)?;
+      }
+      let fits_bits = sub.bits.len() < 1 << 15;
+      let fits_count = subpackages.len() < 1 << 11;
+      match prefer {
+        LengthType::Bits if fits_bits => {
+          bw.write_int(0, 1);
+          bw.write_int(sub.bits.len() as u64, 15);
+        }
+        _ if fits_count => {
+          bw.write_int(1, 1);
+          bw.write_int(subpackages.len() as u64, 11);
+        }
+        _ if fits_bits => {
+          bw.write_int(0, 1);
+          bw.write_int(sub.bits.len() as u64, 15);
+        }
+        _ => return Err(aoc_error("Too many subpackets to encode")),
       }
+      bw.bits.extend(sub.bits);
     }
   }
+  Ok(())
 }
 
-fn parse_input(fname: &str) -> AocResult<Node> {
-  let first_line = std::fs::read_to_string(fname)?
-    .lines()
-    .next()
-    .ok_or(AocError::Custom("Input parse failed".to_owned()))?
-    .to_owned();
-  let mut bs = BitStream::new(first_line);
-  Ok(parse(&mut bs))
+/// Serialize a packet tree as BITS hex, giving the subpackets of operators by
+/// `prefer`red length type where that fits, and by the other one otherwise.
+pub fn encode_with(node: &Node, prefer: LengthType) -> AocResult<String> {
+  let mut bw = BitWriter::default();
+  encode_packet(node, prefer, &mut bw)?;
+  Ok(bw.to_hex())
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let root = parse_input(fname)?;
+/// Serialize a packet tree as BITS hex, preferring the shorter subpacket
+/// counts for operators.
+pub fn encode(node: &Node) -> AocResult<String> { encode_with(node, LengthType::Count) }
 
-  fn traverse(node: &Node) -> i32 {
-    match node {
-      Node::Literal { version, .. } => *version as i32,
-      Node::Operator {
-        version,
-        subpackages,
-        ..
-      } => (*version as i32) + subpackages.iter().map(|x| traverse(x)).sum::<i32>(),
+// Expressions
+
+impl NodeType {
+  /// Name of the operator in expressions.
+  fn name(self) -> &'static str {
+    match self {
+      NodeType::Sum => "sum",
+      NodeType::Prod => "prod",
+      NodeType::Min => "min",
+      NodeType::Max => "max",
+      NodeType::Literal => "literal",
+      NodeType::Greater => "gt",
+      NodeType::Less => "lt",
+      NodeType::Equal => "eq",
     }
   }
 
-  Ok(traverse(&root))
+  /// Symbol of the comparisons, written infix.
+  fn symbol(self) -> Option<&'static str> {
+    match self {
+      NodeType::Greater => Some(">"),
+      NodeType::Less => Some("<"),
+      NodeType::Equal => Some("=="),
+      _ => None,
+    }
+  }
 }
 
-fn part2(fname: &str) -> AocResult<ArithInt> {
-  let root = parse_input(fname)?;
+/// The packet as an expression like `sum(3, prod(4, 5), (6 < 7))`. Versions
+/// are left out.
+impl Display for Node {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Node::Literal { value, .. } => write!(f, "{}", value),
+      Node::Operator { node_type, subpackages, .. } => match (node_type.symbol(), &subpackages[..])
+      {
+        (Some(symbol), [a, b]) => write!(f, "({} {} {})", a, symbol, b),
+        _ => write!(
+          f,
+          "{}({})",
+          node_type.name(),
+          subpackages.iter().format(", ")
+        ),
+      },
+    }
+  }
+}
+
+/// Recursive descent over the text of an expression.
+struct ExprParser<'a> {
+  input: &'a str,
+  rest: &'a str,
+}
+
+impl<'a> ExprParser<'a> {
+  /// The next token: a number, a name, `==` or a single other character.
+  /// Empty at the end of the input.
+  fn peek(&self) -> &'a str {
+    let rest = self.rest.trim_start();
+    let len = match rest.chars().next() {
+      None => 0,
+      Some(c) if c.is_ascii_alphanumeric() => rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len()),
+      Some('=') if rest.starts_with("==") => 2,
+      Some(c) => c.len_utf8(),
+    };
+    &rest[..len]
+  }
+
+  fn next(&mut self) -> &'a str {
+    let token = self.peek();
+    let end = token.as_ptr() as usize + token.len() - self.rest.as_ptr() as usize;
+    self.rest = &self.rest[end..];
+    token
+  }
+
+  fn expect(&mut self, expected: &str) -> AocResult<()> {
+    match self.next() {
+      token if token == expected => Ok(()),
+      token => Err(input_error(
+        self.input,
+        token,
+        format!("Expected '{}'", expected),
+      )),
+    }
+  }
+
+  fn expr(&mut self) -> AocResult<Node> {
+    let token = self.next();
+    if token == "(" {
+      let a = self.expr()?;
+      let symbol = self.next();
+      let node_type = match symbol {
+        ">" => NodeType::Greater,
+        "<" => NodeType::Less,
+        "==" => NodeType::Equal,
+        _ => return Err(input_error(self.input, symbol, "Expected '<', '>' or '=='")),
+      };
+      let b = self.expr()?;
+      self.expect(")")?;
+      return Ok(Node::operator(0, node_type, vec![a, b]));
+    }
+    if token.starts_with(|c: char| c.is_ascii_digit()) {
+      let value = parse_at(self.input, token)?;
+      return Ok(Node::literal(0, value));
+    }
+    let node_type = [
+      NodeType::Sum,
+      NodeType::Prod,
+      NodeType::Min,
+      NodeType::Max,
+      NodeType::Greater,
+      NodeType::Less,
+      NodeType::Equal,
+    ]
+    .into_iter()
+    .find(|node_type| node_type.name() == token)
+    .ok_or_else(|| input_error(self.input, token, "Expected a number, operator or '('"))?;
+    self.expect("(")?;
+    let mut operands = vec![];
+    if self.peek() != ")" {
+      operands.push(self.expr()?);
+      while self.peek() == "," {
+        self.next();
+        operands.push(self.expr()?);
+      }
+    }
+    let close = self.peek();
+    self.expect(")")?;
+    node_type
+      .check_arity(operands.len())
+      .map_err(|msg| input_error(self.input, close, msg))?;
+    Ok(Node::operator(0, node_type, operands))
+  }
+}
+
+/// Compile an expression as printed for a `Node`, e.g.
+/// `sum(3, prod(4, 5), (6 < 7))`, into a packet tree with all versions 0.
+pub fn compile(expr: &str) -> AocResult<Node> {
+  let mut parser = ExprParser { input: expr, rest: expr };
+  let node = parser.expr()?;
+  match parser.next() {
+    "" => Ok(node),
+    token => Err(input_error(
+      expr,
+      token,
+      "Unexpected text after the expression",
+    )),
+  }
+}
 
-  fn traverse(node: &Node) -> ArithInt {
+/// The value of the expression the packet stands for. Errors on operators
+/// with the wrong number of operands, as packets can be built by hand, and on
+/// overflow.
+pub fn evaluate(node: &Node) -> AocResult<ArithInt> {
+  match node {
+    Node::Literal { value, .. } => Ok(*value),
+    Node::Operator { node_type: type_id, subpackages, .. } => {
+      type_id
+        .check_arity(subpackages.len())
+        .map_err(AocError::Custom)?;
+      let sub_eval: Vec<_> = subpackages.iter().map(evaluate).try_collect()?;
+      let overflow = || AocError::Custom(format!("Overflow in {}", node));
+      Ok(match *type_id {
+        NodeType::Sum => sub_eval
+          .into_iter()
+          .try_fold(0, ArithInt::checked_add)
+          .ok_or_else(overflow)?, // sum
+        NodeType::Prod => sub_eval
+          .into_iter()
+          .try_fold(1, ArithInt::checked_mul)
+          .ok_or_else(overflow)?, // prod
+        NodeType::Min => sub_eval.into_iter().min().unwrap(), // minimum
+        NodeType::Max => sub_eval.into_iter().max().unwrap(), // maximum
+        NodeType::Greater => (sub_eval[0] > sub_eval[1]).into(), // greater than
+        NodeType::Less => (sub_eval[0] < sub_eval[1]).into(), // less than
+        NodeType::Equal => (sub_eval[0] == sub_eval[1]).into(), // equal_to
+        NodeType::Literal => unreachable!("operators of literal type take no operands"),
+      })
+    }
+  }
+}
+
+pub fn part1(root: &Node) -> AocResult<i32> {
+  fn traverse(node: &Node) -> i32 {
     match node {
-      Node::Literal { value, .. } => *value,
-      Node::Operator {
-        node_type: type_id,
-        subpackages,
-        ..
-      } => {
-        let sub_eval = subpackages.iter().map(|x| traverse(x)).collect_vec();
-        match *type_id {
-          NodeType::Sum => sub_eval.into_iter().sum(),      // sum
-          NodeType::Prod => sub_eval.into_iter().product(), // prod
-          NodeType::Min => sub_eval.into_iter().min().expect("no elements"), // minimum
-          NodeType::Max => sub_eval.into_iter().max().expect("no elements"), // maximum
-          NodeType::Greater => (sub_eval[0] > sub_eval[1]).into(), // greater than
-          NodeType::Less => (sub_eval[0] < sub_eval[1]).into(), // less than
-          NodeType::Equal => (sub_eval[0] == sub_eval[1]).into(), // equal_to
-          _ => panic!("Unknown type"),
-        }
+      Node::Literal { version, .. } => *version as i32,
+      Node::Operator { version, subpackages, .. } => {
+        (*version as i32) + subpackages.iter().map(traverse).sum::<i32>()
       }
     }
   }
 
-  Ok(traverse(&root))
+  Ok(traverse(root))
+}
+
+pub fn part2(root: &Node) -> AocResult<ArithInt> { evaluate(root) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 16;
+  const TITLE: &'static str = "Packet Decoder";
+  type Input = Node;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+  fn trace(input: &Self::Input) -> AocResult<Option<String>> {
+    Ok(Some(format!("Expression: {}", input)))
+  }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn version_sums() {
+    for (hex, sum) in [
+      ("8A004A801A8002F478", 16),
+      ("620080001611562C8802118E34", 12),
+      ("C0015000016115A2E0802F182340", 23),
+      ("A0016C880162017C3686B18A3D4780", 31),
+    ] {
+      assert_eq!(part1(&parse(hex).unwrap()).unwrap(), sum, "{}", hex);
+    }
+  }
+
+  #[test]
+  fn evaluation() {
+    for (hex, value) in [
+      ("C200B40A82", 3),
+      ("04005AC33890", 54),
+      ("880086C3E88112", 7),
+      ("CE00C43D881120", 9),
+      ("D8005AC2A8F0", 1),
+      ("F600BC2D8F", 0),
+      ("9C005AC2F8F0", 0),
+      ("9C0141080250320F1802104A08", 1),
+    ] {
+      assert_eq!(part2(&parse(hex).unwrap()).unwrap(), value, "{}", hex);
+    }
+  }
+
+  #[test]
+  fn encoding() {
+    // Examples from the puzzle text, each using the length type given.
+    assert_eq!(encode(&Node::literal(6, 2021)).unwrap(), "D2FE28");
+    let node = Node::operator(
+      1,
+      NodeType::Less,
+      vec![Node::literal(6, 10), Node::literal(2, 20)],
+    );
+    assert_eq!(
+      encode_with(&node, LengthType::Bits).unwrap(),
+      "38006F45291200"
+    );
+    let node = Node::operator(
+      7,
+      NodeType::Max,
+      vec![
+        Node::literal(2, 1),
+        Node::literal(4, 2),
+        Node::literal(1, 3),
+      ],
+    );
+    assert_eq!(encode(&node).unwrap(), "EE00D40C823060");
+
+    assert!(encode(&Node::literal(8, 1)).is_err());
+    assert!(encode(&Node::literal(0, -1)).is_err());
+    assert!(encode(&Node::operator(0, NodeType::Literal, vec![])).is_err());
+  }
+
+  #[test]
+  fn expressions() {
+    for (hex, expr) in [
+      ("C200B40A82", "sum(1, 2)"),
+      ("04005AC33890", "prod(6, 9)"),
+      ("880086C3E88112", "min(7, 8, 9)"),
+      ("D8005AC2A8F0", "(5 < 15)"),
+      ("9C0141080250320F1802104A08", "(sum(1, 3) == prod(2, 2))"),
+    ] {
+      let node = parse(hex).unwrap();
+      assert_eq!(node.to_string(), expr);
+      let compiled = compile(expr).unwrap();
+      assert_eq!(compiled.to_string(), expr);
+      assert_eq!(evaluate(&compiled).unwrap(), evaluate(&node).unwrap());
+      let hex = encode(&compiled).unwrap();
+      assert_eq!(parse(&hex).unwrap(), compiled);
+    }
+    let node = compile(" max( 3,prod(4 ,5), gt(2, 1), sum() )").unwrap();
+    assert_eq!(node.to_string(), "max(3, prod(4, 5), (2 > 1), sum())");
+    assert_eq!(evaluate(&node).unwrap(), 20);
+    let err = evaluate(&compile("prod(4294967296, 4294967296)").unwrap()).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("Overflow in prod(4294967296, 4294967296)"),
+      "{}",
+      err
+    );
+    let err = evaluate(&compile("sum(1, 9223372036854775807)").unwrap()).unwrap_err();
+    assert!(err.to_string().contains("Overflow in sum"), "{}", err);
+  }
+
+  #[test]
+  fn compile_errors() {
+    let err = |expr| compile(expr).unwrap_err().to_string();
+    assert!(err("sum(1, 2").contains("Expected ')'"));
+    assert!(err("pow(2, 3)").starts_with("line 1, column 1"));
+    assert!(err("(1 + 2)").contains("Expected '<', '>' or '=='"));
+    assert!(err("lt(1, 2, 3)").contains("Operator lt takes 2 operands, not 3"));
+    assert!(err("min()").contains("takes at least 1 operands"));
+    assert!(err("1 2").starts_with("line 1, column 3: Unexpected text"));
+    assert!(err("").contains("Expected a number"));
+  }
+
+  #[test]
+  fn decode_errors() {
+    fn err(hex: &str) -> String { parse(hex).unwrap_err().to_string() }
+    // Truncated literal: 2021 needs 21 bits.
+    assert!(
+      err("D2F").starts_with("line 1, column 3: bit 11: Expected 5 more bits, but only 1 are left"),
+      "{}",
+      err("D2F")
+    );
+    // Length type 0 operator claiming more bits than there are.
+    assert!(err("38006F45").contains("Subpackets of 27 bits, but only 10 bits are left"));
+    // Literals of 11 and 16 bits in an operator claiming 12 bits.
+    let mut bw = BitWriter::default();
+    for (value, bits) in [(1, 3), (6, 3), (0, 1), (12, 15)] {
+      bw.write_int(value, bits);
+    }
+    encode_packet(&Node::literal(6, 10), LengthType::Count, &mut bw).unwrap();
+    encode_packet(&Node::literal(2, 20), LengthType::Count, &mut bw).unwrap();
+    assert!(err(&bw.to_hex()).contains("Subpackets take 27 bits, expected 12"));
+    // A comparison of one operand.
+    let one = Node::operator(0, NodeType::Less, vec![Node::literal(0, 1)]);
+    let hex = encode(&one).unwrap();
+    assert!(err(&hex).contains("bit 0: Operator lt takes 2 operands, not 1"));
+    assert!(evaluate(&one).is_err());
+    assert!(err("").contains("Empty transmission"));
+    assert!(err("D2FG28").contains("Not a hexadecimal digit"));
+
+    assert!(decode("D2FE28", true).is_ok());
+    let err = decode("D2FE29", true).unwrap_err().to_string();
+    assert!(err.contains("bit 23: Padding has a bit set"), "{}", err);
+    assert!(decode("D2FE29", false).is_ok());
+  }
+
+  #[test]
+  fn round_trip() {
+    let samples = [
+      "8A004A801A8002F478",
+      "C0015000016115A2E0802F182340",
+      "9C0141080250320F1802104A08",
+    ];
+    for hex in samples {
+      let node = parse(hex).unwrap();
+      for prefer in [LengthType::Bits, LengthType::Count] {
+        let encoded = encode_with(&node, prefer).unwrap();
+        assert_eq!(parse(&encoded).unwrap(), node, "{} {:?}", hex, prefer);
+      }
+    }
+    // Deep and wide trees, and literals of all sizes.
+    let mut node = Node::literal(0, 0);
+    for i in 0..40 {
+      let wide = (0..i).map(|j| Node::literal(j as u8 % 8, 1 << j)).collect();
+      node = Node::operator(
+        i as u8 % 8,
+        NodeType::Sum,
+        vec![node, Node::operator(3, NodeType::Prod, wide)],
+      );
+    }
+    for prefer in [LengthType::Bits, LengthType::Count] {
+      let encoded = encode_with(&node, prefer).unwrap();
+      assert_eq!(parse(&encoded).unwrap(), node);
+    }
+    let big = Node::literal(5, ArithInt::MAX);
+    assert_eq!(parse(&encode(&big).unwrap()).unwrap(), big);
+  }
 }
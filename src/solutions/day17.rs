@@ -1,21 +1,34 @@
 use std::fmt::Display;
 
-use crate::error::{AocError, AocResult};
+use crate::error::{input_error, parse_at, AocResult};
+use crate::solutions::Day;
 
-fn parse_input(fname: &str) -> AocResult<((i32, i32), (i32, i32))> {
-  fn parse_interval(s: &str) -> Option<(i32, i32)> {
-    let (l, r) = s[2..].split_once("..")?;
-    let (l, r) = (l.parse::<i32>().ok()?, r.parse::<i32>().ok()?);
-    Some((l, r))
+type Interval = (i32, i32);
+
+pub fn parse(s: &str) -> AocResult<(Interval, Interval)> {
+  let parse_interval = |axis: &str, interval: &str| -> AocResult<Interval> {
+    let (l, r) = interval
+      .strip_prefix(axis)
+      .and_then(|x| x.strip_prefix('='))
+      .and_then(|x| x.split_once(".."))
+      .ok_or_else(|| input_error(s, interval, format!("Expected '{}=min..max'", axis)))?;
+    let (l, r) = (parse_at(s, l)?, parse_at(s, r)?);
+    if l > r {
+      return Err(input_error(s, interval, "Empty interval"));
+    }
+    Ok((l, r))
+  };
+  let line = s.trim();
+  let (x, y) = line
+    .strip_prefix("target area: ")
+    .and_then(|rest| rest.split_once(", "))
+    .ok_or_else(|| input_error(s, line, "Expected 'target area: x=..., y=...'"))?;
+  let (x, y) = (parse_interval("x", x)?, parse_interval("y", y)?);
+  // The search assumes the target is ahead of and below the probe.
+  if x.0 <= 0 || y.1 >= 0 {
+    return Err(input_error(s, line, "Target must have x > 0 and y < 0"));
   }
-  let s = std::fs::read_to_string(fname)?;
-
-  let res = s
-    .trim()
-    .split_once(": ")
-    .and_then(|(_, s)| s.split_once(", "))
-    .and_then(|(s1, s2)| Some((parse_interval(s1)?, parse_interval(s2)?)));
-  res.ok_or(AocError::Custom("Parse error".to_owned()))
+  Ok((x, y))
 }
 
 // Simple binary search helper
@@ -34,7 +47,7 @@ fn bs(mut l: i32, mut r: i32, pred: impl Fn(i32) -> bool) -> (i32, i32) {
 
 fn tri_num(n: i32) -> i32 { n * (n + 1) / 2 }
 
-fn solve(fname: &str) -> AocResult<(i32, i32)> {
+fn solve(&((xmin, xmax), (ymin, ymax)): &(Interval, Interval)) -> (i32, i32) {
   // Assumptions:
   // * Box is to the right and down of (0, 0).
   //   * x could be trivially fixed by reflecting.
@@ -44,8 +57,6 @@ fn solve(fname: &str) -> AocResult<(i32, i32)> {
   // Big insights to be had:
   //   x and y are completely independent
 
-  let ((xmin, xmax), (ymin, ymax)) = parse_input(fname)?;
-
   // Precompute all valid intervals of vx for all relevant time steps.
   // x >= t*(t+1)/2
   // t <= sqrt(2x + 1/4) - 1/2
@@ -95,6 +106,42 @@ fn solve(fname: &str) -> AocResult<(i32, i32)> {
     }
   }
 
-  Ok((y_max, n_hits))
+  (y_max, n_hits)
+}
+
+pub fn part1(target: &(Interval, Interval)) -> AocResult<i32> { Ok(solve(target).0) }
+
+pub fn part2(target: &(Interval, Interval)) -> AocResult<i32> { Ok(solve(target).1) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 17;
+  const TITLE: &'static str = "Trick Shot";
+  type Input = (Interval, Interval);
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+target area: x=20..30, y=-10..-5
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 45);
+    assert_eq!(part2(&input).unwrap(), 112);
+  }
 }
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> { solve(fname) }
@@ -1,18 +1,26 @@
 use std::fmt::{Debug, Display};
+use std::ops::Add;
+use std::str::FromStr;
 
-use itertools::Itertools;
+use crate::error::{aoc_error, input_error, parse_at, AocError, AocResult};
+use crate::parallel;
+use crate::solutions::Day;
 
-use crate::error::{aoc_error, AocResult};
-
-#[derive(Clone, Copy)]
-struct Path {
+/// Where a regular number is in a snailfish number, packed into 32 bits: the
+/// directions taken from the outermost pair, 0 for left and 1 for right, and
+/// their count. A number is a list of these with its regular numbers in order.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Path {
   data: u32,
 }
 
 impl Path {
+  /// The most pairs a path can go into.
+  const MAX_LEN: usize = 32 - 5;
+
   fn new(len: i32, path: u32) -> Self {
     // Low 5 bits len, high bits path
-    debug_assert!(len <= 32 - 5);
+    debug_assert!(len as usize <= Self::MAX_LEN);
     let data = path << 5u32 | (len as u32);
     Self { data }
   }
@@ -35,44 +43,287 @@ impl Debug for Path {
   }
 }
 
-fn parse_input(fname: &str) -> AocResult<Vec<Vec<(Path, i32)>>> {
-  fn parse_line(line: &str) -> Vec<(Path, i32)> {
-    let mut path: u32 = 0;
-    let mut depth = 0;
-    let mut values = vec![];
-    for b in line.bytes() {
-      match b {
-        b'[' => {
-          depth += 1;
-          path = path << 1 | 0;
-        }
-        b']' => {
-          depth -= 1;
-          path >>= 1;
-        }
-        b',' => {
-          path ^= 1;
+/// A number as its regular numbers in order, each with its path from the
+/// root.
+pub type Packed = Vec<(Path, i32)>;
+
+/// A snailfish number as a tree, of any depth and with regular numbers of
+/// any size. The solutions use the packed `Path` form, this is for building,
+/// printing and checking numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnailfishNumber {
+  Regular(i32),
+  Pair(Box<SnailfishNumber>, Box<SnailfishNumber>),
+}
+
+impl SnailfishNumber {
+  pub fn pair(left: SnailfishNumber, right: SnailfishNumber) -> Self {
+    SnailfishNumber::Pair(Box::new(left), Box::new(right))
+  }
+
+  /// Parse a number like `[[1,2],3]` from `line`, a part of `input`, so that
+  /// errors point at the right place of `input`.
+  pub fn parse_in(input: &str, line: &str) -> AocResult<Self> {
+    fn number(input: &str, rest: &mut &str) -> AocResult<SnailfishNumber> {
+      *rest = rest.trim_start();
+      if let Some(after) = rest.strip_prefix('[') {
+        *rest = after;
+        let left = number(input, rest)?;
+        expect(input, rest, ',')?;
+        let right = number(input, rest)?;
+        expect(input, rest, ']')?;
+        return Ok(SnailfishNumber::pair(left, right));
+      }
+      let len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+      if len == 0 {
+        return Err(input_error(input, token(rest), "Expected '[' or a number"));
+      }
+      let value = parse_at(input, &rest[..len])?;
+      *rest = &rest[len..];
+      Ok(SnailfishNumber::Regular(value))
+    }
+    fn expect(input: &str, rest: &mut &str, c: char) -> AocResult<()> {
+      *rest = rest.trim_start();
+      match rest.strip_prefix(c) {
+        Some(after) => {
+          *rest = after;
+          Ok(())
         }
-        b'0'..=b'9' => {
-          let value = (b - b'0') as i32;
-          values.push((Path::new(depth, path), value));
-          ()
+        None => Err(input_error(input, token(rest), format!("Expected '{}'", c))),
+      }
+    }
+    /// The next character, or the empty end of the line.
+    fn token(rest: &str) -> &str { &rest[..rest.chars().next().map_or(0, char::len_utf8)] }
+
+    let mut rest = line;
+    let res = number(input, &mut rest)?;
+    let rest = rest.trim();
+    if !rest.is_empty() {
+      return Err(input_error(input, rest, "Unexpected text after the number"));
+    }
+    Ok(res)
+  }
+
+  /// Number of pairs nested in each other at the deepest point.
+  pub fn depth(&self) -> usize {
+    match self {
+      SnailfishNumber::Regular(_) => 0,
+      SnailfishNumber::Pair(a, b) => 1 + a.depth().max(b.depth()),
+    }
+  }
+
+  pub fn magnitude(&self) -> i64 {
+    match self {
+      SnailfishNumber::Regular(value) => *value as i64,
+      SnailfishNumber::Pair(a, b) => 3 * a.magnitude() + 2 * b.magnitude(),
+    }
+  }
+
+  /// Explode and split until neither applies. For numbers nested deeper than
+  /// the puzzle's, the leftmost pair of two regular numbers nested in four or
+  /// more pairs explodes.
+  pub fn reduce(mut self) -> Self {
+    while self.step().is_some() {}
+    self
+  }
+
+  /// `reduce`, recording every action with the number after it.
+  pub fn reduce_traced(mut self) -> (Self, Vec<ReduceStep>) {
+    let mut steps = vec![];
+    while let Some((action, position)) = self.step() {
+      steps.push(ReduceStep { action, position, result: self.clone() });
+    }
+    (self, steps)
+  }
+
+  /// Do the first action of a reduction, if any, returning it and the
+  /// position of the first regular number it acts on.
+  fn step(&mut self) -> Option<(Action, usize)> {
+    if let Some(exploded) = self.explode(0, &mut 0) {
+      let (left, right) = exploded.pair;
+      return Some((Action::Explode { left, right }, exploded.position));
+    }
+    let (position, value) = self.split(&mut 0)?;
+    Some((Action::Split { value }, position))
+  }
+
+  /// Explode the leftmost pair that should. `index` counts the regular
+  /// numbers passed on the way.
+  fn explode(&mut self, depth: usize, index: &mut usize) -> Option<Exploded> {
+    let SnailfishNumber::Pair(a, b) = self else {
+      *index += 1;
+      return None;
+    };
+    if depth >= 4 {
+      if let (&SnailfishNumber::Regular(l), &SnailfishNumber::Regular(r)) = (&**a, &**b) {
+        *self = SnailfishNumber::Regular(0);
+        return Some(Exploded { position: *index, pair: (l, r), carry: (Some(l), Some(r)) });
+      }
+    }
+    if let Some(mut exploded) = a.explode(depth + 1, index) {
+      if let Some(r) = exploded.carry.1.take() {
+        *b.regular_mut(false) += r;
+      }
+      return Some(exploded);
+    }
+    if let Some(mut exploded) = b.explode(depth + 1, index) {
+      if let Some(l) = exploded.carry.0.take() {
+        *a.regular_mut(true) += l;
+      }
+      return Some(exploded);
+    }
+    None
+  }
+
+  /// The rightmost regular number if `rightmost`, otherwise the leftmost.
+  fn regular_mut(&mut self, rightmost: bool) -> &mut i32 {
+    match self {
+      SnailfishNumber::Regular(value) => value,
+      SnailfishNumber::Pair(a, b) => if rightmost { b } else { a }.regular_mut(rightmost),
+    }
+  }
+
+  /// Split the leftmost regular number of 10 or more, returning its position
+  /// and value.
+  fn split(&mut self, index: &mut usize) -> Option<(usize, i32)> {
+    match self {
+      &mut SnailfishNumber::Regular(value) if value >= 10 => {
+        let half = value / 2;
+        *self = SnailfishNumber::pair(
+          SnailfishNumber::Regular(half),
+          SnailfishNumber::Regular(value - half),
+        );
+        Some((*index, value))
+      }
+      SnailfishNumber::Regular(_) => {
+        *index += 1;
+        None
+      }
+      SnailfishNumber::Pair(a, b) => a.split(index).or_else(|| b.split(index)),
+    }
+  }
+
+  /// The packed form, or `None` if the number is nested too deep for it.
+  pub fn to_packed(&self) -> Option<Packed> {
+    fn leaves(number: &SnailfishNumber, path: Path, res: &mut Packed) {
+      match number {
+        SnailfishNumber::Regular(value) => res.push((path, *value)),
+        SnailfishNumber::Pair(a, b) => {
+          leaves(a, path.lengthen(0), res);
+          leaves(b, path.lengthen(1), res);
         }
-        b' ' => {}
-        t => panic!("Unexpected token '{}'", t as char),
-      };
+      }
     }
-    values
+    if self.depth() > Path::MAX_LEN {
+      return None;
+    }
+    let mut res = vec![];
+    leaves(self, Path::new(0, 0), &mut res);
+    Some(res)
+  }
+
+  /// The number of the packed form, its regular numbers in order, or `None`
+  /// if their depths do not make up a whole number.
+  pub fn from_packed(packed: &[(Path, i32)]) -> Option<Self> {
+    fn build(leaves: &mut &[(Path, i32)], depth: u32) -> Option<SnailfishNumber> {
+      let &(path, value) = leaves.first()?;
+      if path.len() == depth {
+        *leaves = &leaves[1..];
+        return Some(SnailfishNumber::Regular(value));
+      }
+      if path.len() < depth {
+        return None;
+      }
+      let left = build(leaves, depth + 1)?;
+      Some(SnailfishNumber::pair(left, build(leaves, depth + 1)?))
+    }
+    let mut leaves = packed;
+    let number = build(&mut leaves, 0)?;
+    leaves.is_empty().then_some(number)
   }
-  let input = std::fs::read_to_string(fname)?
-    .lines()
-    .map(parse_line)
-    .collect_vec();
-  Ok(input)
 }
 
-fn reduce(vec: Vec<(Path, i32)>) -> Vec<(Path, i32)> {
-  fn explode_index(vec: &mut Vec<(Path, i32)>, i: usize) {
+/// A pair being exploded: where, its values, and the values still to be
+/// added to the regular numbers left and right of it.
+struct Exploded {
+  position: usize,
+  pair: (i32, i32),
+  carry: (Option<i32>, Option<i32>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+  Explode { left: i32, right: i32 },
+  Split { value: i32 },
+}
+
+/// One action of a reduction. `position` counts the regular numbers before
+/// the first one acted on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReduceStep {
+  pub action: Action,
+  pub position: usize,
+  pub result: SnailfishNumber,
+}
+
+/// As in the puzzle text, with what was done where.
+impl Display for ReduceStep {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self.action {
+      Action::Explode { left, right } => write!(
+        f,
+        "after explode [{},{}] at {}: ",
+        left, right, self.position
+      )?,
+      Action::Split { value } => write!(f, "after split {} at {}: ", value, self.position)?,
+    }
+    write!(f, "{}", self.result)
+  }
+}
+
+impl FromStr for SnailfishNumber {
+  type Err = AocError;
+  fn from_str(s: &str) -> AocResult<Self> { SnailfishNumber::parse_in(s, s) }
+}
+
+impl Display for SnailfishNumber {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      SnailfishNumber::Regular(value) => write!(f, "{}", value),
+      SnailfishNumber::Pair(a, b) => write!(f, "[{},{}]", a, b),
+    }
+  }
+}
+
+/// Snailfish addition: the pair of both, reduced.
+impl Add for SnailfishNumber {
+  type Output = SnailfishNumber;
+  fn add(self, other: Self) -> Self { SnailfishNumber::pair(self, other).reduce() }
+}
+
+pub fn parse(s: &str) -> AocResult<Vec<Packed>> {
+  let parse_line = |line: &str| -> AocResult<Packed> {
+    let number = SnailfishNumber::parse_in(s, line)?;
+    // The packed reduction only explodes pairs nested one level deeper.
+    if number.depth() > 4 {
+      return Err(input_error(s, line, "Pairs nested deeper than 4 levels"));
+    }
+    Ok(number.to_packed().unwrap())
+  };
+  s.lines().map(parse_line).collect()
+}
+
+/// An action of a reduction, where it happened and the number after it.
+type PackedStep = (Action, usize, Packed);
+
+fn reduce(vec: Packed) -> Packed { reduce_recording(vec, None) }
+
+/// `reduce`, pushing every explode and split to `steps` if given, in the
+/// order of the puzzle text.
+fn reduce_recording(vec: Packed, mut steps: Option<&mut Vec<PackedStep>>) -> Packed {
+  fn explode_index(vec: &mut Packed, i: usize) {
     let (path, lval) = vec.remove(i);
     let (_, rval) = vec.remove(i);
     vec.insert(i, (path.shorten(), 0));
@@ -85,7 +336,7 @@ fn reduce(vec: Vec<(Path, i32)>) -> Vec<(Path, i32)> {
   }
 
   #[allow(dead_code)]
-  fn explode(vec: &mut Vec<(Path, i32)>) {
+  fn explode(vec: &mut Packed) {
     let mut i = 0;
     while i < vec.len() {
       let (path, _) = &vec[i];
@@ -96,7 +347,7 @@ fn reduce(vec: Vec<(Path, i32)>) -> Vec<(Path, i32)> {
     }
   }
 
-  fn split_and_trim(vec: &mut Vec<(Path, i32)>) {
+  fn split_and_trim(vec: &mut Packed, mut steps: Option<&mut Vec<PackedStep>>) {
     let mut i = 0;
     while i < vec.len() {
       let (_, value) = &vec[i];
@@ -112,31 +363,49 @@ fn reduce(vec: Vec<(Path, i32)>) -> Vec<(Path, i32)> {
       let half = value / 2;
       vec.insert(i, (path.lengthen(1), value - half));
       vec.insert(i, (path.lengthen(0), half));
+      if let Some(steps) = steps.as_deref_mut() {
+        steps.push((Action::Split { value }, i, vec.clone()));
+      }
       if path.len() >= 4 {
         explode_index(vec, i);
-        if i > 0 {
-          i -= 1;
+        if let Some(steps) = steps.as_deref_mut() {
+          let action = Action::Explode { left: half, right: value - half };
+          steps.push((action, i, vec.clone()));
         }
+        i = i.saturating_sub(1);
       }
     }
   }
 
   // This earned mostly nothing
-  let mut res: Vec<(Path, i32)> = vec![];
-  let mut vec_iter = vec.into_iter();
+  let mut res: Packed = vec![];
   let mut add_next = 0;
-  while let Some((path, value)) = vec_iter.next() {
+  let mut j = 0;
+  while j < vec.len() {
+    let (path, value) = vec[j];
     let value = value + add_next;
     add_next = 0;
     if path.len() > 4 {
-      let (_, value2) = vec_iter.next().unwrap();
+      let (_, value2) = vec[j + 1];
+      j += 2;
       if let Some((_, last_value)) = res.last_mut() {
         *last_value += value;
       }
       add_next = value2;
       res.push((path.shorten(), 0));
+      if let Some(steps) = steps.as_deref_mut() {
+        // The number so far, and the rest still to be added to.
+        let mut number = res.clone();
+        number.extend_from_slice(&vec[j..]);
+        if let Some((_, next)) = number.get_mut(res.len()) {
+          *next += add_next;
+        }
+        let action = Action::Explode { left: value, right: value2 };
+        steps.push((action, res.len() - 1, number));
+      }
     } else {
       res.push((path, value));
+      j += 1;
     }
   }
 
@@ -144,24 +413,39 @@ fn reduce(vec: Vec<(Path, i32)>) -> Vec<(Path, i32)> {
   //explode(&mut res);
 
   // This might be higher importance?
-  split_and_trim(&mut res);
+  split_and_trim(&mut res, steps);
   res
 }
 
-fn join(a: &[(Path, i32)], b: &[(Path, i32)]) -> Vec<(Path, i32)> {
+/// `reduce`, recording every action with the number after it.
+pub fn reduce_traced(vec: Packed) -> AocResult<(Packed, Vec<ReduceStep>)> {
+  let mut packed_steps = vec![];
+  let reduced = reduce_recording(vec, Some(&mut packed_steps));
+  let steps = packed_steps
+    .into_iter()
+    .map(|(action, position, number)| {
+      let result =
+        SnailfishNumber::from_packed(&number).ok_or(aoc_error("Invalid packed number"))?;
+      Ok(ReduceStep { action, position, result })
+    })
+    .collect::<AocResult<_>>()?;
+  Ok((reduced, steps))
+}
+
+fn join(a: &[(Path, i32)], b: &[(Path, i32)]) -> Packed {
   let mut res = vec![];
   res.extend(
-    a.into_iter()
+    a.iter()
       .map(|&(path, value)| (path.lengthen_below(0), value)),
   );
   res.extend(
-    b.into_iter()
+    b.iter()
       .map(|&(path, value)| (path.lengthen_below(1), value)),
   );
   res
 }
 
-fn magnitude(vec: &Vec<(Path, i32)>) -> i32 {
+fn magnitude(vec: &[(Path, i32)]) -> i32 {
   vec
     .iter()
     .map(|&(path, value)| {
@@ -174,9 +458,7 @@ fn magnitude(vec: &Vec<(Path, i32)>) -> i32 {
     .sum::<i32>()
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let expressions = parse_input(fname)?;
-
+pub fn part1(expressions: &[Packed]) -> AocResult<i32> {
   let result = expressions
     .iter()
     .cloned()
@@ -186,16 +468,195 @@ fn part1(fname: &str) -> AocResult<i32> {
   Ok(magnitude(&result))
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let expressions = parse_input(fname)?;
-
-  (0..expressions.len())
+pub fn part2(expressions: &[Packed]) -> AocResult<i32> {
+  let pairs = (0..expressions.len())
     .flat_map(|i| (0..expressions.len()).map(move |j| (i, j)))
     .filter(|(i, j)| i != j)
-    .map(|(i, j)| magnitude(&reduce(join(&expressions[i], &expressions[j])))).max()
-    .ok_or(aoc_error("Empty input"))
+    .collect::<Vec<_>>();
+  parallel::par_map(&pairs, |&(i, j)| {
+    magnitude(&reduce(join(&expressions[i], &expressions[j])))
+  })
+  .into_iter()
+  .max()
+  .ok_or(aoc_error("Empty input"))
+}
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 18;
+  const TITLE: &'static str = "Snailfish";
+  type Input = Vec<Packed>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+  fn trace(input: &Self::Input) -> AocResult<Option<String>> {
+    let number = |packed: &[(Path, i32)]| {
+      SnailfishNumber::from_packed(packed).ok_or(aoc_error("Invalid packed number"))
+    };
+    let (first, rest) = input.split_first().ok_or(aoc_error("Empty input"))?;
+    let mut sum = first.clone();
+    let mut out = vec![];
+    for packed in rest {
+      let added = join(&sum, packed);
+      out.push(format!("after addition: {}", number(&added)?));
+      let (reduced, steps) = reduce_traced(added)?;
+      out.extend(steps.iter().map(|step| step.to_string()));
+      sum = reduced;
+    }
+    let sum = number(&sum)?;
+    out.push(format!("Sum: {}, magnitude {}", sum, sum.magnitude()));
+    Ok(Some(out.join("\n")))
+  }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 4140);
+    assert_eq!(part2(&input).unwrap(), 3993);
+  }
+
+  #[test]
+  fn tree() {
+    let sum = SAMPLE
+      .lines()
+      .map(|line| line.parse::<SnailfishNumber>().unwrap())
+      .reduce(|a, b| a + b)
+      .unwrap();
+    assert_eq!(
+      sum.to_string(),
+      "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]"
+    );
+    assert_eq!(sum.magnitude(), 4140);
+
+    let number = |s: &str| s.parse::<SnailfishNumber>().unwrap();
+    assert_eq!(
+      (number("[[[[4,3],4],4],[7,[[8,4],9]]]") + number("[1,1]")).to_string(),
+      "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"
+    );
+    // Deeper than the puzzle and regular numbers above 9.
+    let deep = number(" [[[[[[1,2],3],4],5],6], 17]");
+    assert_eq!(deep.depth(), 6);
+    assert_eq!(deep.to_string(), "[[[[[[1,2],3],4],5],6],17]");
+    assert_eq!(deep.clone().reduce().depth(), 4);
+    assert_eq!(
+      SnailfishNumber::from_packed(&deep.to_packed().unwrap()),
+      Some(deep)
+    );
+    // Packed forms with leaves missing, left over or too shallow.
+    let packed = number("[[1,2],3]").to_packed().unwrap();
+    assert_eq!(SnailfishNumber::from_packed(&[]), None);
+    assert_eq!(SnailfishNumber::from_packed(&packed[..2]), None);
+    assert_eq!(
+      SnailfishNumber::from_packed(&[packed[2], packed[2], packed[2]]),
+      None
+    );
+    assert_eq!(
+      SnailfishNumber::from_packed(&[packed[0], packed[2], packed[2]]),
+      None
+    );
+
+    let err = |s: &str| s.parse::<SnailfishNumber>().unwrap_err().to_string();
+    assert!(err("[1,2").starts_with("line 1, column 5: Expected ']'"));
+    assert!(err("[1;2]").contains("Expected ','"));
+    assert!(err("[1,2]]").contains("Unexpected text"));
+    assert!(err("[x,2]").contains("Expected '[' or a number"));
+    assert!(parse("[[[[[1,2],3],4],5],6]").is_err());
+  }
+
+  #[test]
+  fn worked_example() {
+    let number = |s: &str| s.parse::<SnailfishNumber>().unwrap();
+    let added = SnailfishNumber::pair(number("[[[[4,3],4],4],[7,[[8,4],9]]]"), number("[1,1]"));
+    let (_, steps) = added.clone().reduce_traced();
+    let steps = steps
+      .iter()
+      .map(|step| step.to_string())
+      .collect::<Vec<_>>();
+    // The packed reduction takes the same actions.
+    let (_, packed_steps) = reduce_traced(added.to_packed().unwrap()).unwrap();
+    assert_eq!(
+      packed_steps
+        .iter()
+        .map(|step| step.to_string())
+        .collect::<Vec<_>>(),
+      steps
+    );
+    assert_eq!(
+      steps,
+      [
+        "after explode [4,3] at 0: [[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+        "after explode [8,4] at 4: [[[[0,7],4],[15,[0,13]]],[1,1]]",
+        "after split 15 at 3: [[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+        "after split 13 at 6: [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+        "after explode [6,7] at 6: [[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+      ]
+    );
+  }
+
+  /// Random reduced numbers, as in the puzzle input.
+  fn random_number(seed: &mut u64, depth: usize) -> SnailfishNumber {
+    // xorshift
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    if depth == 4 || seed.is_multiple_of(3) {
+      SnailfishNumber::Regular((*seed >> 8 & 0xffff) as i32 % 10)
+    } else {
+      let left = random_number(seed, depth + 1);
+      SnailfishNumber::pair(left, random_number(seed, depth + 1))
+    }
+  }
+
+  #[test]
+  fn packed_matches_tree() {
+    let mut seed = 0x2021_1218;
+    for _ in 0..500 {
+      let a = random_number(&mut seed, 0);
+      let b = random_number(&mut seed, 0);
+      let (pa, pb) = (a.to_packed().unwrap(), b.to_packed().unwrap());
+      assert_eq!(SnailfishNumber::from_packed(&pa).as_ref(), Some(&a));
+      assert_eq!(a.to_string().parse::<SnailfishNumber>().unwrap(), a);
+
+      let packed = reduce(join(&pa, &pb));
+      let (tree, tree_steps) = SnailfishNumber::pair(a.clone(), b.clone()).reduce_traced();
+      // The fused reduction takes the same actions as the tree, with the same
+      // number after each, and recording them does not change the result.
+      let (recorded, packed_steps) = reduce_traced(join(&pa, &pb)).unwrap();
+      assert_eq!(packed_steps, tree_steps, "{} + {}", a, b);
+      assert_eq!(recorded, packed);
+      assert_eq!(
+        SnailfishNumber::from_packed(&packed).as_ref(),
+        Some(&tree),
+        "{} + {}",
+        a,
+        b
+      );
+      assert_eq!(magnitude(&packed) as i64, tree.magnitude());
+    }
+  }
 }
@@ -1,17 +1,19 @@
 use std::fmt::Display;
 use std::hash::Hash;
-use std::iter;
+use std::sync::OnceLock;
 
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
 
-use crate::error::{aoc_error, AocResult};
+use crate::error::{aoc_error, input_error, parse_at, AocError, AocResult};
+use crate::parallel;
+use crate::solutions::Day;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq)]
-struct PointType {
-  x: i32,
-  y: i32,
-  z: i32,
+pub struct PointType {
+  pub x: i32,
+  pub y: i32,
+  pub z: i32,
 }
 
 impl PointType {
@@ -50,73 +52,244 @@ impl Hash for PointType {
   }
 }
 
-fn parse_input(fname: &str) -> AocResult<Vec<Vec<PointType>>> {
-  fn parse_triple(triple: &str) -> AocResult<PointType> {
-    if let Some((x, y, z)) = triple.split(",").map(|x| x.parse::<i32>()).collect_tuple() {
-      Ok(PointType::new(x?, y?, z?))
-    } else {
-      Err(aoc_error("Wrong number of elements in triple."))
+pub struct Scanners {
+  reports: Vec<Vec<PointType>>,
+  // Both parts need the full alignment, so it is computed once and shared.
+  solved: OnceLock<Alignment>,
+}
+
+impl Scanners {
+  /// Align all scanners to scanner 0 with the puzzle's options.
+  pub fn alignment(&self) -> AocResult<&Alignment> {
+    if let Some(alignment) = self.solved.get() {
+      return Ok(alignment);
     }
+    let alignment = solve(&self.reports, &AlignOptions::default())?;
+    Ok(self.solved.get_or_init(|| alignment))
   }
-  fn parse_section(section: &str) -> AocResult<Vec<PointType>> {
+
+  /// Align all scanners to scanner 0 with other `options`.
+  pub fn align_with(&self, options: &AlignOptions) -> AocResult<Alignment> {
+    solve(&self.reports, options)
+  }
+}
+
+pub fn parse(s: &str) -> AocResult<Scanners> {
+  let parse_triple = |triple: &str| -> AocResult<PointType> {
+    if let Some((x, y, z)) = triple.split(',').collect_tuple() {
+      Ok(PointType::new(
+        parse_at(s, x)?,
+        parse_at(s, y)?,
+        parse_at(s, z)?,
+      ))
+    } else {
+      Err(input_error(s, triple, "Expected 'x,y,z'"))
+    }
+  };
+  let parse_section = |section: &str| -> AocResult<Vec<PointType>> {
     let mut lines = section.lines();
-    lines.next();
-    let res: AocResult<Vec<PointType>> = lines.map(parse_triple).collect();
-    res.map(|mut v| {
-      v.sort();
-      v
-    })
+    match lines.next() {
+      Some(header) if header.starts_with("--- scanner") => {}
+      _ => {
+        return Err(input_error(
+          s,
+          section,
+          "Expected a '--- scanner N ---' header",
+        ))
+      }
+    }
+    let mut v = lines.map(parse_triple).collect::<AocResult<Vec<_>>>()?;
+    v.sort();
+    Ok(v)
+  };
+  let reports = s
+    .trim_end()
+    .split("\n\n")
+    .map(parse_section)
+    .collect::<AocResult<_>>()?;
+  Ok(Scanners { reports, solved: OnceLock::new() })
+}
+
+/// The 24 orientations a scanner can have.
+const MAPPINGS: [fn(PointType) -> PointType; 24] = [
+  |PointType { x, y, z }| PointType::new(x, y, z),
+  |PointType { x, y, z }| PointType::new(-x, -y, z),
+  |PointType { x, y, z }| PointType::new(-x, y, -z),
+  |PointType { x, y, z }| PointType::new(x, -y, -z),
+  |PointType { x, y, z }| PointType::new(y, z, x),
+  |PointType { x, y, z }| PointType::new(-y, -z, x),
+  |PointType { x, y, z }| PointType::new(-y, z, -x),
+  |PointType { x, y, z }| PointType::new(y, -z, -x),
+  |PointType { x, y, z }| PointType::new(z, x, y),
+  |PointType { x, y, z }| PointType::new(-z, -x, y),
+  |PointType { x, y, z }| PointType::new(-z, x, -y),
+  |PointType { x, y, z }| PointType::new(z, -x, -y),
+  |PointType { x, y, z }| PointType::new(y, x, -z),
+  |PointType { x, y, z }| PointType::new(-y, -x, -z),
+  |PointType { x, y, z }| PointType::new(-y, x, z),
+  |PointType { x, y, z }| PointType::new(y, -x, z),
+  |PointType { x, y, z }| PointType::new(z, y, -x),
+  |PointType { x, y, z }| PointType::new(-z, -y, -x),
+  |PointType { x, y, z }| PointType::new(-z, y, x),
+  |PointType { x, y, z }| PointType::new(z, -y, x),
+  |PointType { x, y, z }| PointType::new(x, z, -y),
+  |PointType { x, y, z }| PointType::new(-x, -z, -y),
+  |PointType { x, y, z }| PointType::new(-x, z, y),
+  |PointType { x, y, z }| PointType::new(x, -z, y),
+];
+
+/// A rotation as a matrix, applied to column vectors.
+pub type Rotation = [[i32; 3]; 3];
+
+fn matrix(mapping: fn(PointType) -> PointType) -> Rotation {
+  let columns = [(1, 0, 0), (0, 1, 0), (0, 0, 1)].map(|(x, y, z)| mapping(PointType::new(x, y, z)));
+  let row = |f: fn(&PointType) -> i32| columns.each_ref().map(f);
+  [row(|p| p.x), row(|p| p.y), row(|p| p.z)]
+}
+
+/// Where a scanner is relative to scanner 0: a beacon `p` it reports is at
+/// `rotation * p + translation` as seen from scanner 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transform {
+  pub rotation: Rotation,
+  pub translation: PointType,
+}
+
+impl Transform {
+  pub fn apply(&self, p: PointType) -> PointType {
+    let [x, y, z] = self
+      .rotation
+      .map(|row| row[0] * p.x + row[1] * p.y + row[2] * p.z);
+    PointType::new(x, y, z) + self.translation
   }
-  let s = std::fs::read_to_string(fname)?;
-  s.split("\n\n").map(parse_section).collect()
-}
-
-fn closeness(truth: &[PointType], other: &[PointType]) -> Option<(PointType, Vec<PointType>)> {
-  let mappings: [fn(PointType) -> PointType; 24] = [
-    |PointType { x, y, z }| PointType::new(x, y, z),
-    |PointType { x, y, z }| PointType::new(-x, -y, z),
-    |PointType { x, y, z }| PointType::new(-x, y, -z),
-    |PointType { x, y, z }| PointType::new(x, -y, -z),
-    |PointType { x, y, z }| PointType::new(y, z, x),
-    |PointType { x, y, z }| PointType::new(-y, -z, x),
-    |PointType { x, y, z }| PointType::new(-y, z, -x),
-    |PointType { x, y, z }| PointType::new(y, -z, -x),
-    |PointType { x, y, z }| PointType::new(z, x, y),
-    |PointType { x, y, z }| PointType::new(-z, -x, y),
-    |PointType { x, y, z }| PointType::new(-z, x, -y),
-    |PointType { x, y, z }| PointType::new(z, -x, -y),
-    |PointType { x, y, z }| PointType::new(y, x, -z),
-    |PointType { x, y, z }| PointType::new(-y, -x, -z),
-    |PointType { x, y, z }| PointType::new(-y, x, z),
-    |PointType { x, y, z }| PointType::new(y, -x, z),
-    |PointType { x, y, z }| PointType::new(z, y, -x),
-    |PointType { x, y, z }| PointType::new(-z, -y, -x),
-    |PointType { x, y, z }| PointType::new(-z, y, x),
-    |PointType { x, y, z }| PointType::new(z, -y, x),
-    |PointType { x, y, z }| PointType::new(x, z, -y),
-    |PointType { x, y, z }| PointType::new(-x, -z, -y),
-    |PointType { x, y, z }| PointType::new(-x, z, y),
-    |PointType { x, y, z }| PointType::new(x, -z, y),
-  ];
-
-  for i in 0..mappings.len() {
+}
+
+/// The map reconstructed from all scanners.
+#[derive(Clone, Debug)]
+pub struct Alignment {
+  /// For every scanner, in input order.
+  pub scanners: Vec<Transform>,
+  /// All distinct beacons relative to scanner 0, sorted.
+  pub beacons: Vec<PointType>,
+}
+
+impl Alignment {
+  /// Scanners and beacons as CSV rows of `kind,index,x,y,z`.
+  pub fn to_csv(&self) -> String {
+    let mut out = String::from("kind,index,x,y,z\n");
+    for (kind, points) in self.points() {
+      for (i, p) in points.enumerate() {
+        out += &format!("{},{},{},{},{}\n", kind, i, p.x, p.y, p.z);
+      }
+    }
+    out
+  }
+
+  /// Scanners (red) and beacons (white) as an ASCII PLY point cloud.
+  pub fn to_ply(&self) -> String {
+    let count = self.scanners.len() + self.beacons.len();
+    let mut out = format!(
+      "ply\nformat ascii 1.0\nelement vertex {}\n\
+       property int x\nproperty int y\nproperty int z\n\
+       property uchar red\nproperty uchar green\nproperty uchar blue\nend_header\n",
+      count
+    );
+    for (kind, points) in self.points() {
+      let color = if kind == "scanner" { "255 0 0" } else { "255 255 255" };
+      for p in points {
+        out += &format!("{} {} {} {}\n", p.x, p.y, p.z, color);
+      }
+    }
+    out
+  }
+
+  fn points(&self) -> [(&'static str, Box<dyn Iterator<Item = PointType> + '_>); 2] {
+    [
+      (
+        "scanner",
+        Box::new(self.scanners.iter().map(|t| t.translation)),
+      ),
+      ("beacon", Box::new(self.beacons.iter().copied())),
+    ]
+  }
+
+  fn max_distance(&self) -> i32 {
+    let locations = self.scanners.iter().map(|t| t.translation).collect_vec();
+    locations
+      .iter()
+      .cartesian_product(&locations)
+      .map(|(a, b)| a.l1_dist(b))
+      .max()
+      .unwrap_or(-1)
+  }
+}
+
+/// How scanners are matched with each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlignOptions {
+  /// Number of beacons two scanners need to have in common.
+  pub threshold: usize,
+  /// How far scanners see along each axis. When given, a match is rejected
+  /// if either scanner misses a beacon of the other one within its range.
+  pub range: Option<i32>,
+}
+
+impl Default for AlignOptions {
+  fn default() -> Self { AlignOptions { threshold: 12, range: Some(1000) } }
+}
+
+/// Check every beacon of `cand` moved by `off` against `truth`: enough of
+/// them have to match, and both have to agree on their common range.
+fn verify(truth: &[PointType], cand: &[PointType], off: PointType, options: &AlignOptions) -> bool {
+  let truth_set = truth.iter().collect::<HashSet<_>>();
+  let moved = cand.iter().map(|&p| p + off).collect_vec();
+  let moved_set = moved.iter().collect::<HashSet<_>>();
+  let matched = moved.iter().filter(|p| truth_set.contains(p)).count();
+  if matched < options.threshold {
+    return false;
+  }
+  let Some(range) = options.range else {
+    return true;
+  };
+  let within = |p: PointType, origin: PointType| {
+    let d = p - origin;
+    d.x.abs() <= range && d.y.abs() <= range && d.z.abs() <= range
+  };
+  let origin = PointType::new(0, 0, 0);
+  moved
+    .iter()
+    .all(|&p| !within(p, origin) || truth_set.contains(&p))
+    && truth
+      .iter()
+      .all(|&p| !within(p, off) || moved_set.contains(&p))
+}
+
+/// Find the rotation of `other` that matches `truth`: its index in
+/// `MAPPINGS`, the offset of `other` and its rotated points.
+fn closeness(
+  truth: &[PointType],
+  other: &[PointType],
+  options: &AlignOptions,
+) -> Option<(usize, PointType, Vec<PointType>)> {
+  for (index, mapping) in MAPPINGS.into_iter().enumerate() {
     // The first set is assumed as ground truth.
     // Try all rotations of the other set.
-    let cand = other.iter().map(|&x| mappings[i](x)).collect_vec();
+    let cand = other.iter().map(|&x| mapping(x)).collect_vec();
 
-    // Compute all differences of points and see if get enough equal ones.
-    // (This could technically produce false positives,
-    //  though not likely for large varied data.)
+    // Compute all differences of points; offsets that come up often enough
+    // are candidates, which are then checked beacon by beacon.
     let mut counts = HashMap::with_capacity(1 << 11);
     truth
       .iter()
       .cartesian_product(cand.iter())
       .map(|(&p1, &p2)| p1 - p2)
       .for_each(|pt| *counts.entry(pt).or_insert(0) += 1);
-    if let Some((off, maxi)) = counts.into_iter().max_by(|(_, c1), (_, c2)| c1.cmp(c2)) {
-      if maxi >= 12 {
-        return Some((off, cand));
-      }
+    let found = counts
+      .into_iter()
+      .filter(|&(_, count)| count >= options.threshold)
+      .find(|&(off, _)| verify(truth, &cand, off, options));
+    if let Some((off, _)) = found {
+      return Some((index, off, cand));
     }
   }
   None
@@ -133,8 +306,12 @@ fn distances(points: &[PointType]) -> Vec<i32> {
   dists
 }
 
-fn solve(fname: &str) -> AocResult<(i32, i32)> {
-  let mut input = parse_input(fname)?;
+/// Align all scanners to scanner 0, or tell which ones can not be.
+fn solve(reports: &[Vec<PointType>], options: &AlignOptions) -> AocResult<Alignment> {
+  if options.threshold < 2 {
+    return Err(aoc_error("The overlap threshold must be at least 2"));
+  }
+  let mut input = reports.to_vec();
 
   let input_distances = input.iter().map(|x| distances(x)).collect_vec();
 
@@ -168,46 +345,389 @@ fn solve(fname: &str) -> AocResult<(i32, i32)> {
 
   let mut locations = HashMap::new();
   locations.insert(0, PointType::new(0, 0, 0));
+  // Index into MAPPINGS. Points are rotated once when matched, to scanner 0's
+  // orientation, so this is relative to scanner 0 too.
+  let mut rotations = vec![0; input.len()];
 
+  // Common beacons have all their distances to each other in common too.
+  let pairs = (options.threshold * (options.threshold - 1) / 2) as i32;
   let mut stack = vec![0];
-  let mut seen = iter::repeat(false).take(input.len()).collect_vec();
+  let mut seen = std::iter::repeat_n(false, input.len()).collect_vec();
   while let Some(cur) = stack.pop() {
     seen[cur] = true;
-    for cand_neigh in 0..input.len() {
-      if seen[cand_neigh]
-        || quick_check(&input_distances[cur], &input_distances[cand_neigh]) < 12 * 11 / 2
-      {
-        continue;
-      }
-      if let Some((off, rotated_cand_set)) = closeness(&input[cur], &input[cand_neigh]) {
+    let candidates = (0..input.len())
+      .filter(|&cand_neigh| {
+        !seen[cand_neigh]
+          && !locations.contains_key(&cand_neigh)
+          && quick_check(&input_distances[cur], &input_distances[cand_neigh]) >= pairs
+      })
+      .collect_vec();
+    let matches = parallel::par_map(&candidates, |&cand_neigh| {
+      closeness(&input[cur], &input[cand_neigh], options)
+    });
+    for (cand_neigh, m) in candidates.into_iter().zip(matches) {
+      if let Some((rotation, off, rotated_cand_set)) = m {
         input[cand_neigh] = rotated_cand_set;
+        rotations[cand_neigh] = rotation;
         stack.push(cand_neigh);
         locations.insert(cand_neigh, locations[&cur] + off);
       }
     }
   }
 
-  let points = input
+  let unreached = (0..input.len())
+    .filter(|i| !locations.contains_key(i))
+    .collect_vec();
+  if !unreached.is_empty() {
+    let (noun, verb) = if unreached.len() == 1 { ("Scanner", "is") } else { ("Scanners", "are") };
+    return Err(AocError::Custom(format!(
+      "{} {} {} not connected to scanner 0",
+      noun,
+      unreached.iter().join(", "),
+      verb
+    )));
+  }
+
+  let beacons = input
     .into_iter()
     .enumerate()
-    .map(|(i, points)| {
+    .flat_map(|(i, points)| {
       points
         .into_iter()
         .map(|pt| pt + locations[&i] - locations[&0])
         .collect_vec()
     })
-    .flatten()
+    .sorted()
+    .dedup()
     .collect_vec();
 
-  let res1 = points.iter().unique().count();
+  let scanners = (0..reports.len())
+    .map(|i| Transform {
+      rotation: matrix(MAPPINGS[rotations[i]]),
+      translation: locations[&i] - locations[&0],
+    })
+    .collect();
+  Ok(Alignment { scanners, beacons })
+}
+
+pub fn part1(scanners: &Scanners) -> AocResult<i32> {
+  Ok(scanners.alignment()?.beacons.len() as i32)
+}
+
+pub fn part2(scanners: &Scanners) -> AocResult<i32> { Ok(scanners.alignment()?.max_distance()) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 19;
+  const TITLE: &'static str = "Beacon Scanner";
+  type Input = Scanners;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+  fn trace(input: &Self::Input) -> AocResult<Option<String>> {
+    let alignment = input.alignment()?;
+    let mut out = vec![];
+    for (i, t) in alignment.scanners.iter().enumerate() {
+      let p = t.translation;
+      out.push(format!(
+        "Scanner {} at {},{},{}, rotation {:?}",
+        i, p.x, p.y, p.z, t.rotation
+      ));
+    }
+    out.push(format!("{} beacons", alignment.beacons.len()));
+    Ok(Some(out.join("\n")))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  type Triple = (i32, i32, i32);
+
+  const SAMPLE: &str = "\
+--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
 
-  let res2 = locations
-    .values()
-    .cartesian_product(locations.values())
-    .map(|(a, b)| a.l1_dist(b))
-    .max()
-    .unwrap_or(-1);
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
 
-  Ok((res1 as i32, res2))
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14
+";
+
+  #[test]
+  fn puzzle_sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 79);
+    assert_eq!(part2(&input).unwrap(), 3621);
+    let translations = input
+      .alignment()
+      .unwrap()
+      .scanners
+      .iter()
+      .map(|t| (t.translation.x, t.translation.y, t.translation.z))
+      .collect_vec();
+    assert_eq!(
+      translations,
+      [
+        (0, 0, 0),
+        (68, -1246, -43),
+        (1105, -1205, 1229),
+        (-92, -2380, -20),
+        (-20, -1133, 1061)
+      ]
+    );
+  }
+
+  /// Three scanners at known positions reporting randomly placed beacons in
+  /// their own rotated frames, in the puzzle's input format.
+  const SCANNERS: [Triple; 3] = [(0, 0, 0), (1000, 100, -50), (1900, -200, 100)];
+
+  fn sample() -> String {
+    let scanners = SCANNERS;
+    let rotations: [fn(Triple) -> Triple; 3] =
+      [|p| p, |(x, y, z)| (y, -x, z), |(x, y, z)| (-z, y, x)];
+
+    let mut seed = 2021u64;
+    let mut rand = |lo: i32, hi: i32| {
+      seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+      lo + ((seed >> 33) % (hi - lo) as u64) as i32
+    };
+    let beacons = (0..200)
+      .map(|_| (rand(-1000, 2900), rand(-900, 800), rand(-900, 950)))
+      .collect_vec();
+
+    let mut sections = vec![];
+    for (i, (&(sx, sy, sz), rotate)) in scanners.iter().zip(rotations).enumerate() {
+      let mut section = format!("--- scanner {} ---\n", i);
+      for &(x, y, z) in &beacons {
+        if (x - sx).abs() <= 1000 && (y - sy).abs() <= 1000 && (z - sz).abs() <= 1000 {
+          let (x, y, z) = rotate((x - sx, y - sy, z - sz));
+          section += &format!("{},{},{}\n", x, y, z);
+        }
+      }
+      sections.push(section);
+    }
+    sections.join("\n")
+  }
+
+  #[test]
+  fn synthetic_sample() {
+    let input = parse(&sample()).unwrap();
+    assert_eq!(part1(&input).unwrap(), 200);
+    assert_eq!(part2(&input).unwrap(), 1900 + 200 + 100);
+  }
+
+  #[test]
+  fn transforms_and_export() {
+    let input = parse(&sample()).unwrap();
+    let alignment = input.alignment().unwrap();
+    let translations = alignment
+      .scanners
+      .iter()
+      .map(|t| (t.translation.x, t.translation.y, t.translation.z))
+      .collect_vec();
+    assert_eq!(translations, SCANNERS);
+    // The inverses of the rotations the sample reports were made with.
+    assert_eq!(
+      alignment.scanners[0].rotation,
+      [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+    );
+    assert_eq!(
+      alignment.scanners[1].rotation,
+      [[0, -1, 0], [1, 0, 0], [0, 0, 1]]
+    );
+    assert_eq!(
+      alignment.scanners[2].rotation,
+      [[0, 0, 1], [0, 1, 0], [-1, 0, 0]]
+    );
+    for (report, t) in input.reports.iter().zip(&alignment.scanners) {
+      for &p in report {
+        assert!(alignment.beacons.binary_search(&t.apply(p)).is_ok());
+      }
+    }
+
+    let csv = alignment.to_csv();
+    assert_eq!(csv.lines().count(), 1 + 3 + 200);
+    assert!(csv.contains("\nscanner,1,1000,100,-50\n"));
+    let ply = alignment.to_ply();
+    assert!(ply.contains("element vertex 203\n"));
+    assert!(ply.contains("\n1900 -200 100 255 0 0\n"));
+    assert_eq!(
+      ply.lines().skip_while(|&l| l != "end_header").count(),
+      1 + 203
+    );
+  }
+
+  #[test]
+  fn options_and_errors() {
+    let sample = sample();
+    let input = parse(&sample).unwrap();
+    let options = |threshold, range| AlignOptions { threshold, range };
+    let alignment = input.align_with(&options(3, None)).unwrap();
+    assert_eq!(alignment.beacons.len(), 200);
+    let err = input.align_with(&options(100, Some(1000))).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .contains("Scanners 1, 2 are not connected to scanner 0"),
+      "{}",
+      err
+    );
+
+    // A scanner sharing 12 beacons with scanner 0, but also seeing beacons
+    // next to it that scanner 0 does not.
+    let shared = sample.lines().skip(1).take(12).join("\n");
+    let fake = format!("{}\n--- scanner 3 ---\n{}\n5,5,5\n-7,8,9\n", sample, shared);
+    let input = parse(&fake).unwrap();
+    let err = input.alignment().unwrap_err().to_string();
+    assert!(
+      err.contains("Scanner 3 is not connected to scanner 0"),
+      "{}",
+      err
+    );
+    assert!(part1(&input).is_err());
+    assert_eq!(
+      input.align_with(&options(12, None)).unwrap().scanners.len(),
+      4
+    );
+  }
 }
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> { solve(fname) }
@@ -2,124 +2,141 @@ use std::fmt::Display;
 
 use itertools::Itertools;
 
-use crate::error::{aoc_error, AocResult};
+use crate::error::{input_error, AocResult};
+use crate::solutions::Day;
+use crate::utils::grid::Grid;
 
 #[derive(Clone, Debug)]
-struct Image {
-  height: usize,
-  width: usize,
-  data: Vec<Vec<bool>>,
-  codes: Vec<Vec<u16>>,
+pub struct Image {
+  data: Grid<bool>,
+  codes: Grid<u16>,
 }
 
 impl Image {
-  fn from_string(s: &str, margin: usize) -> Self {
-    let lines = s.lines().collect_vec();
-    let height = lines.len() + 2 * margin;
-    let width = lines[0].len() + 2 * margin;
-
-    let mut image = vec![vec![false; height]; width];
-    lines.iter().enumerate().for_each(|(i, line)| {
-      line
-        .bytes()
-        .enumerate()
-        .for_each(|(j, b)| image[margin + i][margin + j] = b == b'#')
+  /// `image` surrounded by `margin` dark pixels on each side.
+  fn with_margin(image: &Grid<bool>, margin: usize) -> Self {
+    let width = image.width() + 2 * margin;
+    let height = image.height() + 2 * margin;
+    let data = Grid::from_fn(width, height, |(x, y)| {
+      let pos = (x.wrapping_sub(margin), y.wrapping_sub(margin));
+      image.get(pos).copied().unwrap_or(false)
     });
-    let codes = vec![vec![0; height]; width];
-
-    Self {
-      height,
-      width,
-      data: image,
-      codes,
-    }
+    Self { data, codes: Grid::new(width, height, 0) }
   }
 
   fn update_codes(&mut self) {
-    for i in 1..self.height-1 {
-      for j in 1..self.width-1 {
-        self.codes[i][j] = 0;
-      }
-    }
     // 8 7 6   6 6 6
     // 5 4 3 = 3 3 3 + shifts
     // 2 1 0   0 0 0
-    let mut vec = vec![0u16; self.width];
-    for i in 0..2 {
-      for j in 0..self.width {
-        vec[j] = vec[j] << 3 | self.data[i][j] as u16;
+    let mut vec = vec![0u16; self.data.width()];
+    for y in 0..2 {
+      for (v, &b) in vec.iter_mut().zip(self.data.row(y)) {
+        *v = *v << 3 | b as u16;
       }
     }
-    for i in 2..self.height {
-      for j in 0..self.width {
-        vec[j] = (vec[j] & 0b111111) << 3 | self.data[i][j] as u16;
+    for i in 2..self.data.height() {
+      for (v, &b) in vec.iter_mut().zip(self.data.row(i)) {
+        *v = (*v & 0b111111) << 3 | b as u16;
       }
       let mut res: u16 = vec[0] << 1 | vec[1];
-      for j in 2..self.width {
+      for (j, &v) in vec.iter().enumerate().skip(2) {
         // Unset bit 8 5 2 and shift.
-        res = (0b011011011 & res) << 1 | vec[j];
-        self.codes[i-1][j-1] = res;
+        res = (0b011011011 & res) << 1 | v;
+        self.codes[(j - 1, i - 1)] = res;
       }
     }
-    //for i in 1..self.height-1 {
-    //  for j in 1..self.width-1 {
-    //    let mut res = 0u16;
-    //    for ii in i-1..=i+1 {
-    //      for jj in j-1..=j+1 {
-    //        res = res << 1 | self.data[ii][jj] as u16;
-    //      }
-    //    }
-    //    self.codes[i][j] = res;
-    //  }
-    //}
-  }
-
-  fn get_code_at(&self, i: usize, j: usize) -> usize {
-    self.codes[i][j] as usize
   }
 }
 
-// HashMap<(i32, i32)
-fn parse_input(fname: &str, margin: usize) -> AocResult<(Vec<bool>, Image)> {
-  let s = std::fs::read_to_string(fname)?;
+const MARGIN: usize = 102;
 
-  if let [first, second] = s.split("\n\n").collect_vec()[..] {
-    let enc_str = first.bytes().map(|b| b == b'#').collect_vec();
-    Ok((enc_str, Image::from_string(second, margin)))
+pub fn parse(s: &str) -> AocResult<(Vec<bool>, Image)> {
+  let pixel = |c| match c {
+    '#' => Some(true),
+    '.' => Some(false),
+    _ => None,
+  };
+  if let [first, second] = s.trim_end().split("\n\n").collect_vec()[..] {
+    let enc_str = first.chars().map(pixel).collect::<Option<Vec<_>>>();
+    let enc_str = match enc_str {
+      Some(enc_str) if enc_str.len() == 512 => enc_str,
+      _ => return Err(input_error(s, first, "Expected 512 pixels of '#' or '.'")),
+    };
+    let image = Grid::parse_chars_in(s, second, "'#' or '.'", pixel)?;
+    Ok((enc_str, Image::with_margin(&image, MARGIN)))
   } else {
-    Err(aoc_error("Wrong number of sections"))
+    Err(input_error(
+      s,
+      s,
+      "Expected an algorithm and an image separated by an empty line",
+    ))
   }
 }
 
-fn solve(fname: &str) -> AocResult<(i32, i32)> {
-  let margin = 102;
-  let (enc_str, mut image) = parse_input(fname, margin)?;
-
-  fn update(enc_str: &[bool], image: &mut Image) {
-    image.update_codes();
-    for i in 1..image.height - 1 {
-      for j in 1..image.width - 1 {
-        image.data[i][j] = enc_str[image.get_code_at(i, j)]
-      }
+fn update(enc_str: &[bool], image: &mut Image) {
+  image.update_codes();
+  for y in 1..image.data.height() - 1 {
+    for x in 1..image.data.width() - 1 {
+      image.data[(x, y)] = enc_str[image.codes[(x, y)] as usize];
     }
   }
+}
 
-  let result = |image: &Image| {
-    let hmargin = margin/2;
-    let mut res = 0;
-    for i in hmargin..image.height - hmargin {
-      for j in hmargin..image.width - hmargin {
-        res += (image.data[i][j] == true) as i32;
-      }
+fn lit_after((enc_str, image): &(Vec<bool>, Image), steps: usize) -> i32 {
+  let mut image = image.clone();
+  for _ in 0..steps {
+    update(enc_str, &mut image);
+  }
+
+  let hmargin = MARGIN / 2;
+  let mut res = 0;
+  for y in hmargin..image.data.height() - hmargin {
+    for x in hmargin..image.data.width() - hmargin {
+      res += image.data[(x, y)] as i32;
     }
-    res
-  };
+  }
+  res
+}
+
+pub fn part1(input: &(Vec<bool>, Image)) -> AocResult<i32> { Ok(lit_after(input, 2)) }
+
+pub fn part2(input: &(Vec<bool>, Image)) -> AocResult<i32> { Ok(lit_after(input, 50)) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
 
-  for _ in 0..2 { update(enc_str.as_ref(), &mut image); }
-  let res1 = result(&image);
-  for _ in 2..50 { update(enc_str.as_ref(), &mut image); }
-  let res2 = result(&image);
+impl Day for Solver {
+  const DAY: u32 = 20;
+  const TITLE: &'static str = "Trench Map";
+  type Input = (Vec<bool>, Image);
 
-  Ok((res1, res2))
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
+
+#..#.
+#....
+##..#
+..#..
+..###
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 35);
+    assert_eq!(part2(&input).unwrap(), 3351);
+  }
 }
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> { solve(fname) }
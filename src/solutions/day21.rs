@@ -3,19 +3,26 @@ use std::ops::{Index, IndexMut};
 
 use itertools::{iproduct, Itertools};
 
-use crate::error::AocResult;
-
-fn parse_input(fname: &str) -> AocResult<(i32, i32)> {
-  let s = std::fs::read_to_string(fname)?;
-  fn parse(s: &str) -> i32 { s.split(":").last().unwrap().trim().parse().unwrap() }
+use crate::error::{input_error, parse_at, AocResult};
+use crate::solutions::Day;
+
+pub fn parse(s: &str) -> AocResult<(i32, i32)> {
+  let parse_start = |line: &str| -> AocResult<i32> {
+    let (_, start) = line
+      .split_once("starting position: ")
+      .ok_or_else(|| input_error(s, line, "Expected 'Player N starting position: P'"))?;
+    match parse_at(s, start)? {
+      pos @ 1..=10 => Ok(pos),
+      _ => Err(input_error(s, start, "Position must be in 1..=10")),
+    }
+  };
   match s.lines().collect_vec()[..] {
-    [a, b] => Ok((parse(a), parse(b))),
-    _ => panic!("AAA"),
+    [a, b] => Ok((parse_start(a)?, parse_start(b)?)),
+    _ => Err(input_error(s, s, "Expected two players")),
   }
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let (start1, start2) = parse_input(fname)?;
+pub fn part1(&(start1, start2): &(i32, i32)) -> AocResult<i32> {
   let mut throws = (1..=100).cycle();
   let mut player = 0;
   let mut pos = [start1, start2];
@@ -60,26 +67,24 @@ impl Space {
     let ix = s2 + ix * WIN_LIMIT;
     let ix = player + ix * 2;
     let ix = p1 + ix * (MAX_POS + 1);
-    let ix = p2 + ix * (MAX_POS + 1);
-    ix
+    p2 + ix * (MAX_POS + 1)
   }
 }
 
 impl Index<[usize; Space::N_PARAMS]> for Space {
   type Output = IntType;
-  fn index<'a>(&'a self, indices: [usize; Space::N_PARAMS]) -> &'a IntType {
+  fn index(&self, indices: [usize; Space::N_PARAMS]) -> &IntType {
     &self.data[Space::index(indices)]
   }
 }
 
 impl IndexMut<[usize; Space::N_PARAMS]> for Space {
-  fn index_mut<'a>(&'a mut self, indices: [usize; Space::N_PARAMS]) -> &'a mut IntType {
+  fn index_mut(&mut self, indices: [usize; Space::N_PARAMS]) -> &mut IntType {
     &mut self.data[Space::index(indices)]
   }
 }
 
-fn part2(fname: &str) -> AocResult<IntType> {
-  let (start1, start2) = parse_input(fname)?;
+pub fn part2(&(start1, start2): &(i32, i32)) -> AocResult<IntType> {
   let mut space = Space::new();
   space[[0, 0, 1, start1 as usize, start2 as usize]] = 1;
 
@@ -117,6 +122,36 @@ fn part2(fname: &str) -> AocResult<IntType> {
   Ok(win[0].max(win[1]))
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 21;
+  const TITLE: &'static str = "Dirac Dice";
+  type Input = (i32, i32);
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+Player 1 starting position: 4
+Player 2 starting position: 8
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 739785);
+    assert_eq!(part2(&input).unwrap(), 444356092776315);
+  }
 }
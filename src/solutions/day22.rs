@@ -1,8 +1,8 @@
 use std::fmt::Display;
 
-use itertools::Itertools;
-
-use crate::error::AocResult;
+use crate::error::{input_error, AocResult};
+use crate::solutions::Day;
+use crate::utils::boxes::{AxisBox, BoxSet};
 
 #[allow(dead_code)]
 const DEBUG: bool = true;
@@ -17,170 +17,152 @@ macro_rules! debug {
 }
 
 mod parsing {
-  use nom::bytes::complete::{is_not, tag};
+  use nom::branch::alt;
+  use nom::bytes::complete::tag;
   use nom::character::complete::{multispace0, one_of, space0, space1};
-  use nom::combinator::map;
+  use nom::combinator::{consumed, map};
   use nom::multi::separated_list1;
-  use nom::sequence::{delimited, preceded, tuple};
-  use nom::IResult;
+  use nom::sequence::{delimited, preceded};
+  use nom::{IResult, Parser};
 
-  use super::Cuboid;
+  /// The text of a range, with its bounds.
+  pub(crate) type Range<'a> = (&'a str, i32, i32);
 
   fn on_off(s: &str) -> IResult<&str, bool> {
-    map(delimited(space0, is_not(" "), space1), |s| {
-      if s == "on" {
-        true
-      } else {
-        false
-      }
-    })(s)
+    map(
+      delimited(space0, alt((tag("on"), tag("off"))), space1),
+      |s| s == "on",
+    )
+    .parse(s)
   }
 
-  fn range(s: &str) -> IResult<&str, (i32, i32)> {
-    let (s, (_coord, _, l, _, r)) = tuple((
+  fn range(s: &str) -> IResult<&str, Range<'_>> {
+    let (s, (text, (_coord, _, l, _, r))) = consumed((
       one_of("xyz"),
       tag("="),
       nom::character::complete::i32,
       tag(".."),
       nom::character::complete::i32,
-    ))(s)?;
-    Ok((s, (l, r)))
+    ))
+    .parse(s)?;
+    Ok((s, (text, l, r)))
   }
 
-  fn instruction(s: &str) -> IResult<&str, (bool, Option<Cuboid>)> {
-    let (rem, (on_off, xrange, yrange, zrange)) = tuple((
+  fn instruction(s: &str) -> IResult<&str, (bool, [Range<'_>; 3])> {
+    let (rem, (on_off, xrange, yrange, zrange)) = (
       on_off,
       range,
       preceded(tag(","), range),
       preceded(tag(","), range),
-    ))(s)?;
-    Ok((
-      rem,
-      (
-        on_off,
-        Cuboid::new(
-          xrange.0,
-          xrange.1 + 1,
-          yrange.0,
-          yrange.1 + 1,
-          zrange.0,
-          zrange.1 + 1,
-          1,
-        ),
-      ),
-    ))
+    )
+      .parse(s)?;
+    Ok((rem, (on_off, [xrange, yrange, zrange])))
   }
 
-  pub(crate) fn parse(s: &str) -> IResult<&str, Vec<(bool, Option<Cuboid>)>> {
+  pub(crate) fn parse(s: &str) -> IResult<&str, Vec<(bool, [Range<'_>; 3])>> {
     delimited(
       multispace0,
       separated_list1(tag("\n"), instruction),
       multispace0,
-    )(s)
+    )
+    .parse(s)
   }
 }
 
-fn parse_input(fname: &str) -> AocResult<Vec<(bool, Option<Cuboid>)>> {
-  let s = std::fs::read_to_string(fname)?;
-  let (rem, result) = parsing::parse(&s)?;
-  assert!(rem.is_empty());
-  Ok(result)
+pub fn parse(s: &str) -> AocResult<Vec<(bool, Cuboid)>> {
+  // Point at the first line that could not be parsed.
+  fn first_line(rest: &str) -> &str { rest.trim_start().lines().next().unwrap_or_default() }
+  match parsing::parse(s) {
+    Ok(("", result)) => result
+      .into_iter()
+      .map(|(on, ranges)| {
+        if let Some(&(text, ..)) = ranges.iter().find(|&&(_, l, r)| l > r) {
+          return Err(input_error(s, text, "Empty range"));
+        }
+        let min = ranges.map(|(_, l, _)| i64::from(l));
+        let max = ranges.map(|(_, _, r)| i64::from(r));
+        Ok((on, Cuboid::inclusive(min, max).unwrap()))
+      })
+      .collect(),
+    Ok((rest, _)) => Err(input_error(s, first_line(rest), "Invalid instruction")),
+    Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+      Err(input_error(s, first_line(err.input), "Invalid instruction"))
+    }
+    Err(nom::Err::Incomplete(_)) => Err(input_error(s, &s[s.len()..], "Incomplete input")),
+  }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) struct Cuboid {
-  xmin: i32,
-  xmax: i32,
-  ymin: i32,
-  ymax: i32,
-  zmin: i32,
-  zmax: i32,
-  sign: i32,
-}
+/// Reactor cubes from the low corner up to, but excluding, the high one.
+pub type Cuboid = AxisBox<3>;
 
-impl Cuboid {
-  fn new(
-    xmin: i32,
-    xmax: i32,
-    ymin: i32,
-    ymax: i32,
-    zmin: i32,
-    zmax: i32,
-    sign: i32,
-  ) -> Option<Self> {
-    if xmax <= xmin || ymax <= ymin || zmax <= zmin {
-      None
+fn solve(instructions: impl Iterator<Item = (bool, Cuboid)>) -> i64 {
+  let mut reactor = BoxSet::new();
+  for (on, cuboid) in instructions {
+    if on {
+      reactor.insert(cuboid);
     } else {
-      Some(Self { xmin, xmax, ymin, ymax, zmin, zmax, sign })
+      reactor.remove(&cuboid);
     }
   }
-  fn volume(&self) -> i64 {
-    ((self.xmax - self.xmin) as i64)
-      * ((self.ymax - self.ymin) as i64)
-      * ((self.zmax - self.zmin) as i64)
-      * (self.sign as i64)
-  }
-  fn intersect(&self, other: &Cuboid) -> Option<Self> {
-    let xmin = self.xmin.max(other.xmin);
-    let xmax = self.xmax.min(other.xmax);
-    let ymin = self.ymin.max(other.ymin);
-    let ymax = self.ymax.min(other.ymax);
-    let zmin = self.zmin.max(other.zmin);
-    let zmax = self.zmax.min(other.zmax);
-    Self::new(xmin, xmax, ymin, ymax, zmin, zmax, -self.sign * other.sign)
-  }
+  reactor.volume()
 }
 
-struct CuboidCollection {
-  cuboids: Vec<Cuboid>,
-  volume: i64,
+pub fn part1(instructions: &[(bool, Cuboid)]) -> AocResult<i64> {
+  let region = Cuboid::inclusive([-50; 3], [50; 3]).unwrap();
+  let instructions = instructions
+    .iter()
+    .filter_map(|&(on, cuboid)| Some((on, cuboid.intersect(&region)?)));
+  Ok(solve(instructions))
 }
 
-impl CuboidCollection {
-  fn add(&mut self, cuboid: Cuboid, is_add: bool) {
-    let mut intersecting_cuboids = vec![];
-    for existing_cuboid in &self.cuboids {
-      if let Some(intersection) = existing_cuboid.intersect(&cuboid) {
-        self.volume += intersection.volume();
-        intersecting_cuboids.push(intersection);
-      }
-    }
-    self.cuboids.extend(intersecting_cuboids);
-    if is_add {
-      self.volume += cuboid.volume();
-      self.cuboids.push(cuboid);
-    }
-  }
+pub fn part2(instructions: &[(bool, Cuboid)]) -> AocResult<i64> {
+  Ok(solve(instructions.iter().copied()))
 }
 
-fn solve(instructions: Vec<(bool, Option<Cuboid>)>) -> i64 {
-  let mut cc = CuboidCollection { cuboids: vec![], volume: 0 };
-  for (value, cuboid) in instructions {
-    if let Some(cuboid) = cuboid {
-      if value {
-        cc.add(cuboid, true);
-      } else {
-        cc.add(cuboid, false);
-      }
-    }
-  }
-  cc.volume
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
 }
 
-fn part1(fname: &str) -> AocResult<i64> {
-  let bounding_box = Cuboid::new(-50, 51, -50, 51, -50, 51, -1).unwrap();
-  let instructions = parse_input(fname)?
-    .into_iter()
-    .map(|(value, cuboid)| (value, cuboid.and_then(|x| x.intersect(&bounding_box))))
-    .collect_vec();
-  Ok(solve(instructions))
-}
+pub struct Solver;
 
-fn part2(fname: &str) -> AocResult<i64> {
-  let instructions = parse_input(fname)?;
-  Ok(solve(instructions))
+impl Day for Solver {
+  const DAY: u32 = 22;
+  const TITLE: &'static str = "Reactor Reboot";
+  type Input = Vec<(bool, Cuboid)>;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 39);
+    assert_eq!(part2(&input).unwrap(), 39);
+  }
+
+  #[test]
+  fn empty_range() {
+    let err = parse("on x=1..2,y=1..1,z=3..3\noff x=10..12,y=12..10,z=10..12\n")
+      .unwrap_err()
+      .to_string();
+    assert!(
+      err.starts_with("line 2, column 14: Empty range 'y=12..10'"),
+      "{}",
+      err
+    );
+  }
 }
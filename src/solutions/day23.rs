@@ -1,11 +1,12 @@
 use std::fmt::Display;
-use std::fs;
+use std::hash::{Hash, Hasher};
 
-use binary_heap_plus::BinaryHeap;
-use hashbrown::HashMap;
 use itertools::Itertools;
 
-use crate::error::{aoc_error, AocResult};
+use crate::error::{aoc_error, input_error, AocError, AocResult};
+use crate::solutions::Day;
+use crate::utils::grid::{Grid, Pos};
+use crate::utils::search::{self, Cost, Path, SearchProblem};
 
 #[allow(dead_code)]
 const DEBUG: bool = true;
@@ -31,14 +32,13 @@ macro_rules! debug {
 // C - 100
 // D - 1000
 //
-// One numbering:
+// Nodes are the cells a pod can stop on, numbered in reading order. The
+// hallway cells right above the rooms are not nodes, pods only pass them.
 //
 //  01.2.3.4.56
 //    7 8 9 A
 //    B C D E
 //
-//
-//
 // Things to note:
 // Because D is so much higher you can allow 10 moves for every 1 move of the cheaper kind.
 // A detour with D is at least 2 extra length, so 20 moves.
@@ -47,6 +47,16 @@ macro_rules! debug {
 // needs to happen for D and work downwards.
 //
 
+/// Bit sets of nodes are `u128`s.
+const MAX_NODES: usize = 128;
+/// Distance between nodes with no path between them, small enough to add two.
+const UNREACHABLE: u32 = u32::MAX / 2;
+/// Energy per step grows tenfold per kind, which has to fit a `Cost`.
+const MAX_ROOMS: usize = 16;
+
+/// The rows folded into the diagram for part 2.
+const FOLDED: [&str; 2] = ["DCBA", "DBAC"];
+
 #[derive(PartialEq)]
 enum PodIsWhere {
   Hallway,
@@ -54,91 +64,201 @@ enum PodIsWhere {
   OtherRoom,
 }
 
-#[repr(u8)]
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
-enum PodKind {
-  Amber = 0,
-  Bronze = 1,
-  Copper = 2,
-  Desert = 3,
-}
-
-impl PodKind {
-  fn from(b: u8) -> PodKind {
-    match b {
-      b'A' => PodKind::Amber,
-      b'B' => PodKind::Bronze,
-      b'C' => PodKind::Copper,
-      b'D' => PodKind::Desert,
-      _ => unreachable!("Pod outside ABCD"),
-    }
-  }
-}
-
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 struct Amphipod {
-  kind: PodKind,
-  location: i8,
+  /// 0 for A, 1 for B, ... and also the index of the room it belongs in.
+  kind: u8,
+  location: u8,
   id: u8,
   moves: i8,
 }
 
 impl Amphipod {
-  fn whereis(&self) -> PodIsWhere {
-    use PodIsWhere::*;
-    if self.location <= 6 {
-      Hallway
-    } else {
-      let f = |x: i8, m: i8| if x >= 0x7 && (x - 0x7) % 4 == m { CorrectRoom } else { OtherRoom };
-      f(self.location, self.kind as i8)
+  fn letter(&self) -> char { (b'A' + self.kind) as char }
+  fn multiplier(&self) -> Cost { 10u64.pow(self.kind as u32) }
+}
+
+/// The burrow diagram from the puzzle input, with any number of rooms of any
+/// depth along a hallway of any length.
+#[derive(Clone, Debug)]
+pub struct Burrow {
+  diagram: Grid<char>,
+}
+
+impl Burrow {
+  fn from_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+    let lines = lines
+      .into_iter()
+      .map(|line| line.chars().collect_vec())
+      .collect_vec();
+    let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    let diagram = Grid::from_fn(width, lines.len(), |(x, y)| {
+      lines[y].get(x).copied().unwrap_or(' ')
+    });
+    Burrow { diagram }
+  }
+
+  /// The burrow with the two extra rows of part 2 inserted below the top
+  /// row of the rooms, if it has the four rooms these are made for.
+  fn unfold(&self) -> Option<Burrow> {
+    let rows = self
+      .diagram
+      .rows()
+      .map(|row| row.iter().collect::<String>())
+      .collect_vec();
+    let room_row = rows
+      .iter()
+      .position(|row| row.contains(|c: char| c.is_ascii_uppercase()))?;
+    let walls = rows[room_row].replace(|c: char| c.is_ascii_uppercase(), ".");
+    let room_xs = walls.match_indices('.').map(|(x, _)| x).collect_vec();
+    if room_xs.len() != FOLDED[0].len() {
+      return None;
     }
+    let folded = FOLDED.map(|pods| {
+      let mut row = walls.clone().into_bytes();
+      for (&x, pod) in room_xs.iter().zip(pods.bytes()) {
+        row[x] = pod;
+      }
+      String::from_utf8(row).unwrap()
+    });
+    let lines = rows[..=room_row]
+      .iter()
+      .chain(&folded)
+      .chain(&rows[room_row + 1..])
+      .map(|row| row.as_str());
+    Some(Burrow::from_lines(lines))
+  }
+
+  fn unfold_or_err(&self) -> AocResult<Burrow> {
+    self
+      .unfold()
+      .ok_or(aoc_error("Unfolding needs a burrow of four rooms"))
   }
-  fn multiplier(&self) -> i32 { 10i32.pow(self.kind as u32) }
 }
 
-fn parse_input(fname: &str) -> AocResult<Vec<Amphipod>> {
-  let s = fs::read_to_string(fname)?;
-  let letters = s.bytes().filter(|b| b"ABCD".contains(b)).collect_vec();
-  match letters[..] {
-    [a, b, c, d, e, f, g, h] => Ok(vec![
-      Amphipod { kind: PodKind::from(a), location: 0x7, id: 0, moves: 0 },
-      Amphipod { kind: PodKind::from(b), location: 0x8, id: 1, moves: 0 },
-      Amphipod { kind: PodKind::from(c), location: 0x9, id: 2, moves: 0 },
-      Amphipod { kind: PodKind::from(d), location: 0xA, id: 3, moves: 0 },
-      Amphipod { kind: PodKind::from(e), location: 0xB, id: 4, moves: 0 },
-      Amphipod { kind: PodKind::from(f), location: 0xC, id: 5, moves: 0 },
-      Amphipod { kind: PodKind::from(g), location: 0xD, id: 6, moves: 0 },
-      Amphipod { kind: PodKind::from(h), location: 0xE, id: 7, moves: 0 },
-    ]),
-    _ => Err(aoc_error("Wrong number of things")),
+pub fn parse(s: &str) -> AocResult<Burrow> {
+  for line in s.lines() {
+    if let Some((i, c)) = line
+      .char_indices()
+      .find(|&(_, c)| !matches!(c, '#' | '.' | ' ' | 'A'..='Z'))
+    {
+      return Err(input_error(
+        s,
+        &line[i..i + c.len_utf8()],
+        "Unexpected character",
+      ));
+    }
   }
+  let burrow = Burrow::from_lines(s.lines());
+  // Check that the burrow makes sense, pointing at the problem if not.
+  System::new(&burrow.diagram).map_err(|(pos, msg)| {
+    let line = s.lines().nth(pos.1).unwrap_or(s);
+    let at = line
+      .char_indices()
+      .nth(pos.0)
+      .map_or(line, |(i, c)| &line[i..i + c.len_utf8()]);
+    input_error(s, at, msg)
+  })?;
+  Ok(burrow)
 }
 
-struct System<const N: usize> {
+/// The graph of nodes of a burrow and where the amphipods start.
+struct System {
+  /// Diagram position of each node.
+  nodes: Vec<Pos>,
   init_pods: Vec<Amphipod>,
-  dist: [[i8; N]; N],
-  conn: Vec<Vec<i8>>,
-  init_blocked: [bool; N],
-  burrows: [Vec<i8>; 4],
+  /// Number of steps between any two nodes.
+  dist: Vec<Vec<u32>>,
+  conn: Vec<Vec<u8>>,
+  hallway: u128,
+  /// The nodes of each room, from the top down.
+  burrows: Vec<Vec<u8>>,
+  /// The diagram without amphipods.
+  empty: Grid<char>,
 }
 
-impl<const N: usize> System<N> {
-  fn new(pods: Vec<Amphipod>, connections: Vec<(i8, i8, i8)>, burrows: [Vec<i8>; 4]) -> Self {
-    let mut conn = vec![vec![]; N];
-    let mut dist = [[42i8; N]; N];
-    for (a, b, d) in connections {
-      conn[a as usize].push(b);
-      conn[b as usize].push(a);
-      dist[a as usize][b as usize] = d;
-      dist[b as usize][a as usize] = d;
+impl System {
+  /// The system of `diagram`, or the position of what is wrong with it.
+  fn new(diagram: &Grid<char>) -> Result<Self, (Pos, String)> {
+    let open = |pos: Pos| diagram.get(pos).is_some_and(|&c| c != '#' && c != ' ');
+    let hallway_y = (0..diagram.height())
+      .find(|&y| (0..diagram.width()).any(|x| open((x, y))))
+      .ok_or(((0, 0), "No hallway".to_owned()))?;
+    let room_xs = (0..diagram.width())
+      .filter(|&x| open((x, hallway_y)) && open((x, hallway_y + 1)))
+      .collect_vec();
+    if room_xs.is_empty() {
+      return Err(((0, hallway_y), "No rooms below the hallway".to_owned()));
+    }
+    if room_xs.len() > MAX_ROOMS {
+      return Err(((0, hallway_y), format!("More than {} rooms", MAX_ROOMS)));
+    }
+
+    // Number the nodes in reading order, rooms are columns below the hallway.
+    let mut burrows = vec![vec![]; room_xs.len()];
+    let mut index = Grid::new(diagram.width(), diagram.height(), None);
+    let mut nodes = vec![];
+    for pos @ (x, y) in diagram.positions().filter(|&pos| open(pos)) {
+      let room = room_xs.iter().position(|&room_x| room_x == x);
+      if y == hallway_y && room.is_some() {
+        if diagram[pos] != '.' {
+          return Err((pos, "Amphipod blocking the entrance of a room".to_owned()));
+        }
+        continue;
+      }
+      if y != hallway_y {
+        let above = (x, y - 1);
+        let in_room = room.is_some() && (y == hallway_y + 1 || index[above].is_some());
+        if !in_room || !open(above) {
+          return Err((
+            pos,
+            "Cell is neither in the hallway nor in a room".to_owned(),
+          ));
+        }
+        burrows[room.unwrap()].push(nodes.len() as u8);
+      }
+      index[pos] = Some(nodes.len() as u8);
+      nodes.push(pos);
     }
-    for i in 0..N {
-      dist[i][i] = 0;
+    if nodes.len() > MAX_NODES {
+      return Err(((0, 0), format!("More than {} cells", MAX_NODES)));
+    }
+
+    // Connect nodes that are adjacent, or only separated by room entrances.
+    let n = nodes.len();
+    let mut conn = vec![vec![]; n];
+    let mut dist = vec![vec![UNREACHABLE; n]; n];
+    for (a, &start) in nodes.iter().enumerate() {
+      dist[a][a] = 0;
+      let mut frontier = vec![start];
+      let mut seen = vec![start];
+      for steps in 1.. {
+        let mut next = vec![];
+        for pos in frontier {
+          for neigh in diagram.neighbours4(pos).filter(|&p| open(p)) {
+            if seen.contains(&neigh) {
+              continue;
+            }
+            seen.push(neigh);
+            match index[neigh] {
+              Some(b) => {
+                conn[a].push(b);
+                dist[a][b as usize] = steps;
+              }
+              None => next.push(neigh),
+            }
+          }
+        }
+        if next.is_empty() {
+          break;
+        }
+        frontier = next;
+      }
     }
     // Floyd-Warshall, all pairs shortest paths
-    for k in 0..N {
-      for i in 0..N {
-        for j in 0..N {
+    for k in 0..n {
+      for i in 0..n {
+        for j in 0..n {
           if dist[i][j] > dist[i][k] + dist[k][j] {
             dist[i][j] = dist[i][k] + dist[k][j];
           }
@@ -146,67 +266,121 @@ impl<const N: usize> System<N> {
       }
     }
 
-    let mut blocked = [false; N];
-    for pod in &pods {
-      blocked[pod.location as usize] = true;
+    let mut init_pods = vec![];
+    for (node, &pos) in nodes.iter().enumerate() {
+      if let c @ 'A'..='Z' = diagram[pos] {
+        let kind = c as u8 - b'A';
+        if kind as usize >= burrows.len() {
+          return Err((pos, format!("No room for amphipods of kind {}", c)));
+        }
+        let id = init_pods.len() as u8;
+        // Pods in the hallway have made their first move already.
+        let moves = (pos.1 == hallway_y) as i8;
+        init_pods.push(Amphipod { kind, location: node as u8, id, moves });
+      }
     }
+    for (kind, burrow) in burrows.iter().enumerate() {
+      if init_pods
+        .iter()
+        .filter(|pod| pod.kind as usize == kind)
+        .count()
+        > burrow.len()
+      {
+        let c = (b'A' + kind as u8) as char;
+        return Err((
+          nodes[burrow[0] as usize],
+          format!("Too many amphipods of kind {}", c),
+        ));
+      }
+    }
+
+    let hallway = (0..n)
+      .filter(|&i| nodes[i].1 == hallway_y)
+      .fold(0, |set, i| set | 1 << i);
+    let empty = diagram.map(|&c| if c.is_ascii_uppercase() { '.' } else { c });
+    Ok(System { nodes, init_pods, dist, conn, hallway, burrows, empty })
+  }
 
-    Self { init_pods: pods, dist, conn, init_blocked: blocked, burrows }
+  fn from_burrow(burrow: &Burrow) -> AocResult<Self> {
+    System::new(&burrow.diagram).map_err(|((x, y), msg)| {
+      AocError::Custom(format!("{} at line {}, column {}", msg, y + 1, x + 1))
+    })
   }
 
-  fn initial_state(&self) -> State<N> {
-    State {
-      pods: self.init_pods.clone(),
-      blocked: self.init_blocked,
-      cost_w_heuristic: 0,
-      cost: 0,
-      burrows: self.burrows.clone(),
+  fn whereis(&self, pod: &Amphipod) -> PodIsWhere {
+    if self.hallway & 1 << pod.location != 0 {
+      PodIsWhere::Hallway
+    } else if self.burrows[pod.kind as usize].contains(&pod.location) {
+      PodIsWhere::CorrectRoom
+    } else {
+      PodIsWhere::OtherRoom
     }
   }
-}
 
-#[derive(Clone, Debug)]
-struct State<const N: usize> {
-  cost_w_heuristic: i32,
-  cost: i32,
-  blocked: [bool; N],
-  pods: Vec<Amphipod>,
-  burrows: [Vec<i8>; 4],
-}
+  fn initial_state(&self) -> State {
+    let blocked = self
+      .init_pods
+      .iter()
+      .fold(0, |set, pod| set | 1 << pod.location);
+    let unfilled = self
+      .burrows
+      .iter()
+      .map(|burrow| burrow.len() as u8)
+      .collect();
+    State { pods: self.init_pods.clone(), blocked, unfilled }
+  }
 
-impl<const N: usize> State<N> {
-  fn solved(&self) -> bool {
-    self
+  /// The deepest node of the room of `kind` that is still to be filled.
+  fn next_slot(&self, state: &State, kind: u8) -> Option<u8> {
+    let unfilled = state.unfilled[kind as usize] as usize;
+    self.burrows[kind as usize][..unfilled].last().copied()
+  }
+
+  /// Cost of moving every misplaced pod to the top of its room, ignoring
+  /// all other pods.
+  fn heuristic(&self, state: &State) -> Cost {
+    state
+      .pods
+      .iter()
+      .filter(|&pod| self.whereis(pod) != PodIsWhere::CorrectRoom)
+      .map(|pod| {
+        let top_pos = self.burrows[pod.kind as usize][0] as usize;
+        (self.dist[pod.location as usize][top_pos] as Cost) * pod.multiplier()
+      })
+      .sum()
+  }
+
+  fn solved(&self, state: &State) -> bool {
+    state
       .pods
       .iter()
-      .all(|pod| pod.whereis() == PodIsWhere::CorrectRoom)
+      .all(|pod| self.whereis(pod) == PodIsWhere::CorrectRoom)
   }
-  fn board_snapshot(&self) -> Vec<i8> { self.pods.iter().map(|pod| pod.location).collect_vec() }
-  fn get_moves(&self, state: &State<N>, system: &System<N>) -> Vec<(u8, i8)> {
+
+  fn get_moves(&self, state: &State) -> Vec<(u8, u8)> {
     use PodIsWhere::*;
     let mut moves = vec![];
-    for pod in &self.pods {
+    for pod in &state.pods {
       if pod.moves >= 2 {
         continue;
       }
-      let mut stack = vec![(1 << pod.location, pod.location)];
+      let mut stack = vec![(1u128 << pod.location, pod.location)];
       while let Some((visited, cur)) = stack.pop() {
         if cur != pod.location {
           let mut cand_pod = *pod;
           cand_pod.location = cur;
-          let burrow = &state.burrows[pod.kind as usize];
-          if (pod.whereis() == Hallway && cur == *burrow.last().unwrap())
-            || (pod.whereis() != Hallway && cand_pod.whereis() == Hallway)
+          if (self.whereis(pod) == Hallway && self.next_slot(state, pod.kind) == Some(cur))
+            || (self.whereis(pod) != Hallway && self.whereis(&cand_pod) == Hallway)
           {
             moves.push((pod.id, cur));
           }
         }
         let mut new_visited = visited;
-        for &neigh in &system.conn[cur as usize] {
-          new_visited |= 1i32 << neigh;
+        for &neigh in &self.conn[cur as usize] {
+          new_visited |= 1 << neigh;
         }
-        for &neigh in &system.conn[cur as usize] {
-          if visited & 1i32 << neigh == 0 && !self.blocked[neigh as usize] {
+        for &neigh in &self.conn[cur as usize] {
+          if visited & 1 << neigh == 0 && state.blocked & 1 << neigh == 0 {
             stack.push((new_visited, neigh));
           }
         }
@@ -216,188 +390,271 @@ impl<const N: usize> State<N> {
   }
 }
 
-fn solve<const N: usize>(system: System<N>) -> Option<State<N>> {
-  let mut init = system.initial_state();
-  for (i, burrow) in init.burrows.iter_mut().enumerate() {
-    loop {
-      let mut popped = false;
-      for pod in &mut init.pods {
-        if pod.kind as usize != i {
-          continue;
-        }
-        if let Some(leaf_burrow) = burrow.last() {
-          if pod.location == *leaf_burrow {
-            pod.moves = 2; // hack
-            burrow.pop();
-            popped = true;
-            break;
-          }
-        }
-      }
-      if !popped {
-        break;
-      }
-    }
-  }
-  let mut seen = HashMap::new();
-  seen.insert(system.initial_state().board_snapshot(), 0);
-  let mut pq = BinaryHeap::new_by(|a: &State<N>, b: &State<N>| {
-    a.cost_w_heuristic.cmp(&b.cost_w_heuristic).reverse()
-  });
-  pq.push(init);
-  let mut res = None;
-  while let Some(state) = pq.pop() {
-    // Success case
-    if state.solved() {
-      res = Some(state);
-      break;
-    }
+impl SearchProblem for System {
+  type State = State;
 
-    // Calc moves and expand
-    let moves = state.get_moves(&state, &system);
-    for (id, target) in moves {
+  fn successors(&self, state: &State) -> impl Iterator<Item = (State, Cost)> {
+    self.get_moves(state).into_iter().map(|(id, target)| {
       let mut new_state = state.clone();
       let mut pod = new_state.pods[id as usize];
       pod.moves += 1;
-
-      // Cost update
-      new_state.cost +=
-        (system.dist[pod.location as usize][target as usize] as i32) * pod.multiplier();
-      new_state.cost_w_heuristic = new_state.cost
-        + new_state
-          .pods
-          .iter()
-          .filter(|&pod| pod.whereis() != PodIsWhere::CorrectRoom)
-          .map(|pod| {
-            let top_pos = (pod.kind as usize) + 0x7;
-            (system.dist[pod.location as usize][top_pos] as i32)*pod.multiplier()
-          })
-          .sum::<i32>();
+      let cost = (self.dist[pod.location as usize][target as usize] as Cost) * pod.multiplier();
 
       // Location update
-      new_state.blocked[pod.location as usize] = false;
+      new_state.blocked &= !(1 << pod.location);
       pod.location = target;
-      new_state.blocked[pod.location as usize] = true;
+      new_state.blocked |= 1 << pod.location;
 
       // Update burrows
-      if pod.moves == 2 {
-        let burrow = &mut new_state.burrows[pod.kind as usize];
-        if let Some(leaf_burrow) = burrow.last() {
-          if target == *leaf_burrow {
-            burrow.pop();
-          }
-        }
+      if pod.moves == 2 && self.next_slot(&new_state, pod.kind) == Some(target) {
+        new_state.unfilled[pod.kind as usize] -= 1;
       }
       new_state.pods[id as usize] = pod;
+      (new_state, cost)
+    })
+  }
+
+  fn is_goal(&self, state: &State) -> bool { self.solved(state) }
+}
+
+#[derive(Clone, Debug)]
+struct State {
+  /// Set of nodes with a pod on them.
+  blocked: u128,
+  pods: Vec<Amphipod>,
+  /// Number of nodes of each room still to be filled, from the top down.
+  unfilled: Vec<u8>,
+}
 
-      let snap = new_state.board_snapshot();
-      let mini = seen.entry(snap).or_insert(i32::MAX);
-      if new_state.cost < *mini {
-        *mini = new_state.cost;
-        pq.push(new_state);
+/// States are told apart by where the pods are only.
+impl PartialEq for State {
+  fn eq(&self, other: &Self) -> bool {
+    self
+      .pods
+      .iter()
+      .zip(&other.pods)
+      .all(|(a, b)| a.location == b.location)
+  }
+}
+
+impl Eq for State {}
+
+impl Hash for State {
+  fn hash<H: Hasher>(&self, hasher: &mut H) {
+    for pod in &self.pods {
+      pod.location.hash(hasher);
+    }
+  }
+}
+
+fn solve(system: &System) -> Option<Path<State>> {
+  let mut init = system.initial_state();
+  // Pods already at the bottom of their own room never have to move.
+  for kind in 0..system.burrows.len() as u8 {
+    while let Some(slot) = system.next_slot(&init, kind) {
+      let Some(pod) = init.pods.iter_mut().find(|pod| pod.location == slot) else {
+        break;
+      };
+      if pod.kind != kind {
+        break;
       }
+      pod.moves = 2;
+      init.unfilled[kind as usize] -= 1;
     }
   }
-  res
+  let heuristic = |state: &State| system.heuristic(state);
+  search::astar(system, init, heuristic)
 }
 
-//  01.2.3.4.56
-//    7 8 9 A
-//    B C D E
-fn part1(fname: &str) -> AocResult<i32> {
-  let pods = parse_input(fname)?;
-
-  let connections = vec![
-    (0x0, 0x1, 1),
-    (0x1, 0x2, 2),
-    (0x2, 0x3, 2),
-    (0x3, 0x4, 2),
-    (0x4, 0x5, 2),
-    (0x5, 0x6, 1),
-    //
-    (0x1, 0x7, 2),
-    (0x2, 0x7, 2),
-    (0x2, 0x8, 2),
-    (0x3, 0x8, 2),
-    (0x3, 0x9, 2),
-    (0x4, 0x9, 2),
-    (0x4, 0xA, 2),
-    (0x5, 0xA, 2),
-    //
-    (0x7, 0xB, 1),
-    (0x8, 0xC, 1),
-    (0x9, 0xD, 1),
-    (0xA, 0xE, 1),
-  ];
+/// One step of the cheapest solution: pod number `pod` moves from node `from`
+/// to node `to`, using `cost` energy. Nodes are numbered as drawn at the top
+/// of this file, and pods in reading order of the diagram.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+  pub pod: u8,
+  pub from: u8,
+  pub to: u8,
+  pub cost: u64,
+}
 
-  let burrows = [
-    vec![0x7, 0xB],
-    vec![0x8, 0xC],
-    vec![0x9, 0xD],
-    vec![0xA, 0xE],
-  ];
+fn moves(system: &System, states: &[State]) -> Vec<Move> {
+  states
+    .windows(2)
+    .filter_map(|w| {
+      let (before, after) = w[0]
+        .pods
+        .iter()
+        .zip(&w[1].pods)
+        .find(|(a, b)| a.location != b.location)?;
+      let dist = system.dist[before.location as usize][after.location as usize] as Cost;
+      Some(Move {
+        pod: before.id,
+        from: before.location,
+        to: after.location,
+        cost: dist * before.multiplier(),
+      })
+    })
+    .collect()
+}
 
-  let system = System::<0xF>::new(pods, connections, burrows);
-  let res = solve(system);
-  Ok(if let Some(state) = res { state.cost } else { -1 })
+/// The moves of the cheapest way to organize the amphipods of part 1.
+pub fn part1_moves(burrow: &Burrow) -> AocResult<Vec<Move>> {
+  let system = System::from_burrow(burrow)?;
+  let path = solve(&system).ok_or(aoc_error("No solution"))?;
+  Ok(moves(&system, &path.states))
 }
 
-fn part2(fname: &str) -> AocResult<i32> {
-  let mut pods = parse_input(fname)?;
-
-  let connections = vec![
-    (0x0, 0x1, 1),
-    (0x1, 0x2, 2),
-    (0x2, 0x3, 2),
-    (0x3, 0x4, 2),
-    (0x4, 0x5, 2),
-    (0x5, 0x6, 1),
-    //
-    (0x1, 0x7, 2),
-    (0x2, 0x7, 2),
-    (0x2, 0x8, 2),
-    (0x3, 0x8, 2),
-    (0x3, 0x9, 2),
-    (0x4, 0x9, 2),
-    (0x4, 0xA, 2),
-    (0x5, 0xA, 2),
-    //
-    (0x07, 0x0F, 1),
-    (0x08, 0x10, 1),
-    (0x09, 0x11, 1),
-    (0x0A, 0x12, 1),
-    //
-    (0x0F, 0x13, 1),
-    (0x10, 0x14, 1),
-    (0x11, 0x15, 1),
-    (0x12, 0x16, 1),
-    //
-    (0x13, 0x0B, 1),
-    (0x14, 0x0C, 1),
-    (0x15, 0x0D, 1),
-    (0x16, 0x0E, 1),
-  ];
+/// The moves of the cheapest way to organize the amphipods of part 2.
+pub fn part2_moves(burrow: &Burrow) -> AocResult<Vec<Move>> {
+  part1_moves(&burrow.unfold_or_err()?)
+}
 
-  let burrows = [
-    vec![0x07, 0x0F, 0x13, 0x0B],
-    vec![0x08, 0x10, 0x14, 0x0C],
-    vec![0x09, 0x11, 0x15, 0x0D],
-    vec![0x0A, 0x12, 0x16, 0x0E],
+/// The burrow diagram of `state`, as in the puzzle text.
+fn render(system: &System, state: &State) -> String {
+  let mut diagram = system.empty.clone();
+  for pod in &state.pods {
+    diagram[system.nodes[pod.location as usize]] = pod.letter();
+  }
+  diagram.to_string().lines().map(str::trim_end).join("\n")
+}
+
+/// Every move of the cheapest solution with the diagram after it.
+fn trace_moves(burrow: &Burrow) -> AocResult<String> {
+  let system = System::from_burrow(burrow)?;
+  let path = solve(&system).ok_or(aoc_error("No solution"))?;
+  let mut out = vec![
+    format!("Energy {}:", path.cost),
+    render(&system, &system.initial_state()),
   ];
-  pods.push(Amphipod { kind: PodKind::from(b'D'), location: 0x0F, id: 8, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'C'), location: 0x10, id: 9, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'B'), location: 0x11, id: 10, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'A'), location: 0x12, id: 11, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'D'), location: 0x13, id: 12, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'B'), location: 0x14, id: 13, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'A'), location: 0x15, id: 14, moves: 0 });
-  pods.push(Amphipod { kind: PodKind::from(b'C'), location: 0x16, id: 15, moves: 0 });
-
-  let system = System::<0x17>::new(pods, connections, burrows);
-  let res = solve(system);
-  Ok(if let Some(state) = res { state.cost } else { -1 })
+  for (m, state) in moves(&system, &path.states).iter().zip(&path.states[1..]) {
+    let letter = state.pods[m.pod as usize].letter();
+    out.push(format!(
+      "\nPod {} ({}) moves from {} to {}, energy {}:",
+      m.pod, letter, m.from, m.to, m.cost
+    ));
+    out.push(render(&system, state));
+  }
+  Ok(out.join("\n"))
+}
+
+fn min_energy(burrow: &Burrow) -> AocResult<i64> {
+  let system = System::from_burrow(burrow)?;
+  Ok(solve(&system).map_or(-1, |path| path.cost as i64))
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, part2(fname)?))
+pub fn part1(burrow: &Burrow) -> AocResult<i64> { min_energy(burrow) }
+
+/// Part 2 only applies to burrows of four rooms, other layouts get a note
+/// instead of failing the whole day.
+pub fn part2(burrow: &Burrow) -> AocResult<String> {
+  match burrow.unfold() {
+    Some(unfolded) => Ok(min_energy(&unfolded)?.to_string()),
+    None => Ok(NOT_APPLICABLE.to_owned()),
+  }
+}
+
+const NOT_APPLICABLE: &str = "n/a (unfolding needs a burrow of four rooms)";
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 23;
+  const TITLE: &'static str = "Amphipod";
+  type Input = Burrow;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+  fn trace(input: &Self::Input) -> AocResult<Option<String>> {
+    let part1 = trace_moves(input)?;
+    let part2 = match input.unfold() {
+      Some(unfolded) => trace_moves(&unfolded)?,
+      None => NOT_APPLICABLE.to_owned(),
+    };
+    Ok(Some(format!("Part 1\n{}\n\nPart 2\n{}", part1, part2)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 12521);
+    assert_eq!(part2(&input).unwrap(), "44169");
+  }
+
+  #[test]
+  fn sample_moves() {
+    let input = parse(SAMPLE).unwrap();
+    let moves = part1_moves(&input).unwrap();
+    assert_eq!(moves.iter().map(|m| m.cost).sum::<u64>(), 12521);
+    // Every pod ends up where the next move of that pod starts.
+    for (i, m) in moves.iter().enumerate() {
+      if let Some(next) = moves[i + 1..].iter().find(|n| n.pod == m.pod) {
+        assert_eq!(next.from, m.to);
+      }
+    }
+    assert_eq!(
+      part2_moves(&input)
+        .unwrap()
+        .iter()
+        .map(|m| m.cost)
+        .sum::<u64>(),
+      44169
+    );
+  }
+
+  #[test]
+  fn other_layouts() {
+    // Three rooms of depth three, and a hallway wider than the rooms.
+    let rooms = |top, hallway| {
+      format!(
+        "###########\n#{}#\n###{}###\n  #A#B#C#\n  #A#B#C#\n  #######\n",
+        hallway, top
+      )
+    };
+    let input = parse(&rooms("A#B#.", ".......C.")).unwrap();
+    assert_eq!(part1(&input).unwrap(), 200);
+    let input = parse(&rooms("A#C#B", ".........")).unwrap();
+    assert_eq!(part1(&input).unwrap(), 20 + 400 + 40);
+    assert_eq!(part2(&input).unwrap(), NOT_APPLICABLE);
+    assert!(part2_moves(&input).is_err());
+    // Single slots, with a longer hallway.
+    let input = parse("###########\n#.........#\n####B#A####\n   #####\n").unwrap();
+    assert_eq!(part1(&input).unwrap(), 46);
+    // A hallway longer than the distances of the puzzle.
+    let long = format!(
+      "{0}\n#A{1}#\n{2}.#\n{0}\n",
+      "#".repeat(130),
+      ".".repeat(127),
+      "#".repeat(128)
+    );
+    assert_eq!(part1(&parse(&long).unwrap()).unwrap(), 128);
+  }
+
+  #[test]
+  fn bad_diagrams() {
+    let err = |s| parse(s).unwrap_err().to_string();
+    assert!(err("#####\n#...#\n##C##\n").contains("No room for amphipods of kind C"));
+    assert!(err("#####\n#A..#\n##A##\n#####\n").contains("Too many"));
+    assert!(err("#####\n#.x.#\n").contains("Unexpected character"));
+    assert!(err("#####\n#...#\n#####\n").contains("No rooms"));
+    assert!(err("#####\n#.A.#\n##.##\n").contains("entrance"));
+  }
 }
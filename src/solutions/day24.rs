@@ -1,9 +1,12 @@
 use std::fmt::Display;
+use std::sync::OnceLock;
 
 use hashbrown::HashSet;
 use itertools::Itertools;
 
-use crate::error::AocResult;
+use crate::alu::{parse_program, Instr, Machine, Operand};
+use crate::error::{aoc_error, input_error, AocResult};
+use crate::solutions::Day;
 
 #[allow(dead_code)]
 const DEBUG: bool = true;
@@ -17,15 +20,94 @@ macro_rules! debug {
   };
 }
 
+/// Whether the program accepts `digits` as a model number, ending with z = 0.
+pub fn accepts(program: &[Instr], digits: &[i64]) -> AocResult<bool> {
+  let mut machine = Machine::new();
+  machine.run(program, &mut digits.iter().copied())?;
+  Ok(machine.z() == 0)
+}
+
+/// The block MONAD runs for every digit, with the parameters as `{}`.
+const BLOCK: &str = "\
+inp w
+mul x 0
+add x z
+mod x 26
+div z {}
+add x {}
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y {}
+mul y x
+add z y
+";
+
+/// The effect of one MONAD block with parameters `a`, `b` and `c` on z.
 fn op(mut z: i64, input: i64, a: i64, b: i64, c: i64) -> i64 {
   let w = input;
   let x = (z % 26 + b != w) as i64;
   z /= a;
-  z + x * (z*25 + (w + c))
+  z + x * (z * 25 + (w + c))
 }
 
-fn solve() -> AocResult<(String, String)> {
-  fn brute_all((a, b, c): (i64, i64, i64), targets: &HashSet<i64>) -> HashSet<i64> {
+type Params = (i64, i64, i64);
+
+/// The parameters of each digit, if `program` is made of MONAD blocks only.
+fn extract_params(program: &[Instr]) -> Option<Vec<Params>> {
+  let template = parse_program(&BLOCK.replace("{}", "0")).unwrap();
+  let slots = BLOCK
+    .lines()
+    .positions(|line| line.ends_with("{}"))
+    .collect_vec();
+  let param = |instr: &Instr| match *instr {
+    Instr::Div(_, Operand::Imm(v)) | Instr::Add(_, Operand::Imm(v)) => Some(v),
+    _ => None,
+  };
+  if program.is_empty() || !program.len().is_multiple_of(template.len()) {
+    return None;
+  }
+  program
+    .chunks(template.len())
+    .map(|block| {
+      for (j, (instr, expected)) in block.iter().zip(&template).enumerate() {
+        let same_shape = std::mem::discriminant(instr) == std::mem::discriminant(expected);
+        if instr != expected && !(slots.contains(&j) && same_shape && param(instr).is_some()) {
+          return None;
+        }
+      }
+      let [a, b, c] = [0, 1, 2].map(|k| param(&block[slots[k]]).unwrap());
+      // Blocks that pop z (a = 26) are the only ones that can shrink it.
+      (a == 1 || a == 26).then_some((a, b, c))
+    })
+    .collect()
+}
+
+pub struct Monad {
+  program: Vec<Instr>,
+  /// The per-digit parameters if the program has the usual MONAD shape.
+  args: Option<Vec<Params>>,
+  // Both parts search through the same sets of valid z values, so they are computed once.
+  valid: OnceLock<Vec<HashSet<i64>>>,
+}
+
+pub fn parse(s: &str) -> AocResult<Monad> {
+  let program = parse_program(s)?;
+  if !program.iter().any(|instr| matches!(instr, Instr::Inp(_))) {
+    return Err(input_error(s, s, "Program reads no input"));
+  }
+  let args = extract_params(&program);
+  Ok(Monad { program, args, valid: OnceLock::new() })
+}
+
+fn valid_states(args: &[Params]) -> Vec<HashSet<i64>> {
+  fn brute_all((a, b, c): Params, targets: &HashSet<i64>) -> HashSet<i64> {
     let mut res = HashSet::new();
     for d in 1..=9 {
       for z in 0..=500000 {
@@ -37,8 +119,117 @@ fn solve() -> AocResult<(String, String)> {
     }
     res
   }
-  
-  let args = [
+
+  let mut v_brute = HashSet::from_iter([0]);
+  let mut valid = vec![v_brute.clone()];
+  for i in (1..args.len()).rev() {
+    v_brute = brute_all(args[i], &v_brute);
+    valid.push(v_brute.clone());
+  }
+  valid.reverse();
+  valid
+}
+
+// Greedily pick the first digit in `order` that keeps z in a valid state.
+fn find_model_blocks(monad: &Monad, args: &[Params], order: &[i64]) -> Option<Vec<i64>> {
+  let valid = monad.valid.get_or_init(|| valid_states(args));
+  let mut digits = vec![];
+  let mut z = 0;
+  for (i, &(a, b, c)) in args.iter().enumerate() {
+    let &d = order
+      .iter()
+      .find(|&&d| valid[i].contains(&op(z, d, a, b, c)))?;
+    z = op(z, d, a, b, c);
+    digits.push(d);
+  }
+  Some(digits)
+}
+
+/// Depth first search over the digits of any program, remembering the
+/// register states at the start of a block from which no model is valid.
+fn find_model_generic(program: &[Instr], order: &[i64]) -> AocResult<Option<Vec<i64>>> {
+  let starts = program
+    .iter()
+    .positions(|instr| matches!(instr, Instr::Inp(_)));
+  let mut bounds = starts.collect_vec();
+  bounds.push(program.len());
+  let mut machine = Machine::new();
+  machine.run(&program[..bounds[0]], &mut std::iter::empty())?;
+  let blocks = bounds
+    .windows(2)
+    .map(|w| &program[w[0]..w[1]])
+    .collect_vec();
+
+  fn search(
+    blocks: &[&[Instr]],
+    machine: Machine,
+    order: &[i64],
+    dead: &mut HashSet<(usize, Machine)>,
+    digits: &mut Vec<i64>,
+  ) -> bool {
+    let Some((block, rest)) = blocks.split_first() else {
+      return machine.z() == 0;
+    };
+    if dead.contains(&(blocks.len(), machine)) {
+      return false;
+    }
+    for &d in order {
+      // Programs erroring on a digit do not accept it.
+      let mut next = machine;
+      if next.run(block, &mut std::iter::once(d)).is_err() {
+        continue;
+      }
+      digits.push(d);
+      if search(rest, next, order, dead, digits) {
+        return true;
+      }
+      digits.pop();
+    }
+    dead.insert((blocks.len(), machine));
+    false
+  }
+
+  let mut digits = vec![];
+  let found = search(&blocks, machine, order, &mut HashSet::new(), &mut digits);
+  Ok(found.then_some(digits))
+}
+
+fn find_model(monad: &Monad, order: &[i64]) -> AocResult<String> {
+  let digits = match &monad.args {
+    Some(args) => find_model_blocks(monad, args, order),
+    None => find_model_generic(&monad.program, order)?,
+  };
+  let digits = digits.ok_or(aoc_error("No valid model number"))?;
+  Ok(digits.iter().join(""))
+}
+
+pub fn part1(monad: &Monad) -> AocResult<String> { find_model(monad, &[9, 8, 7, 6, 5, 4, 3, 2, 1]) }
+
+pub fn part2(monad: &Monad) -> AocResult<String> { find_model(monad, &[1, 2, 3, 4, 5, 6, 7, 8, 9]) }
+
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
+}
+
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 24;
+  const TITLE: &'static str = "Arithmetic Logic Unit";
+  type Input = Monad;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Parameters of the MONAD program these days were first solved for.
+  const ARGS: [Params; 14] = [
     (1, 13, 8),
     (1, 12, 13),
     (1, 12, 8),
@@ -55,40 +246,67 @@ fn solve() -> AocResult<(String, String)> {
     (26, -4, 7),
   ];
 
-  let mut v_brute = HashSet::from_iter([0]);
-  let mut valid = vec![v_brute.clone()];
-  for i in (1..args.len()).rev() {
-    v_brute = brute_all(args[i], &v_brute);
-    valid.push(v_brute.clone());
+  fn monad_program(args: &[Params]) -> String {
+    args
+      .iter()
+      .map(|&(a, b, c)| {
+        let mut block = BLOCK.to_owned();
+        for v in [a, b, c] {
+          block = block.replacen("{}", &v.to_string(), 1);
+        }
+        block
+      })
+      .collect()
   }
-  valid.reverse();
 
-  let mut min_digits = vec![];
-  let mut max_digits = vec![];
-  let mut max_z = 0;
-  let mut min_z = 0;
-  for i in 0..args.len() {
-    let (a, b, c) = args[i];
-    for d in (1..=9).rev() {
-      let nz = op(max_z, d, a, b, c);
-      if valid[i].contains(&nz) {
-        max_z = nz;
-        max_digits.push(d);
-        break;
-      }
-    }
-    for d in 1..=9 {
-      let nz = op(min_z, d, a, b, c);
-      if valid[i].contains(&nz) {
-        min_z = nz;
-        min_digits.push(d);
-        break;
+  fn digits(model: &str) -> Vec<i64> { model.bytes().map(|d| (d - b'0') as i64).collect() }
+
+  #[test]
+  fn op_matches_machine() {
+    for (a, b, c) in ARGS {
+      let block = parse_program(&monad_program(&[(a, b, c)])).unwrap();
+      for z in [0, 1, 7, 25, 26, 27, 300, 12345, 26 * 26 * 26 + 5] {
+        for d in 1..=9 {
+          let mut machine = Machine { regs: [0, 0, 0, z] };
+          machine.run(&block, &mut std::iter::once(d)).unwrap();
+          assert_eq!(
+            machine.z(),
+            op(z, d, a, b, c),
+            "{:?} z={} d={}",
+            (a, b, c),
+            z,
+            d
+          );
+        }
       }
     }
   }
-  Ok((max_digits.iter().join(""), min_digits.iter().join("")))
-}
 
-pub fn run(_fname: &str) -> AocResult<(impl Display, impl Display)> {
-  solve()
+  #[test]
+  fn models_are_valid() {
+    let monad = parse(&monad_program(&ARGS)).unwrap();
+    assert_eq!(monad.args.as_deref(), Some(&ARGS[..]));
+    let largest = part1(&monad).unwrap();
+    let smallest = part2(&monad).unwrap();
+    assert!(accepts(&monad.program, &digits(&largest)).unwrap());
+    assert!(accepts(&monad.program, &digits(&smallest)).unwrap());
+    assert!(smallest <= largest);
+    // The blocks agree with running the program.
+    let z = digits(&largest)
+      .into_iter()
+      .zip(ARGS)
+      .fold(0, |z, (d, (a, b, c))| op(z, d, a, b, c));
+    assert_eq!(z, 0);
+  }
+
+  #[test]
+  fn generic_fallback() {
+    // Valid when the second digit is the first plus two.
+    let monad = parse("inp w\ninp x\nadd w 2\neql w x\neql w 0\nadd z w\n").unwrap();
+    assert!(monad.args.is_none());
+    assert_eq!(part1(&monad).unwrap(), "79");
+    assert_eq!(part2(&monad).unwrap(), "13");
+    let monad = parse("inp w\nadd z 1\n").unwrap();
+    assert!(part1(&monad).is_err());
+  }
 }
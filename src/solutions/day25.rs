@@ -1,81 +1,100 @@
-use std::{fs, fmt::Display};
+use std::fmt::Display;
 
 use crate::error::AocResult;
+use crate::solutions::Day;
+use crate::utils::grid::{Grid, Pos};
 
-fn parse_input(fname: &str) -> AocResult<(Vec<(usize, usize)>,Vec<(usize, usize)>,Vec<Vec<bool>>)> {
-  let contents = fs::read_to_string(fname)?;
-
-  let mut east = vec![];
-  let mut south = vec![];
-  let mut occupied = vec![vec![false; contents.lines().next().unwrap().len()]; contents.lines().count()];
-  
-  for (y, line) in contents.lines().enumerate() {
-    for (x, b) in line.bytes().enumerate() {
-      match b {
-        b'>' => {
-          east.push((x, y));
-          occupied[y][x] = true;
-        },
-        b'v' => {
-          south.push((x, y));
-          occupied[y][x] = true;
-        },
-        _ => (),
-      }
+type Herd = Vec<Pos>;
+
+type Seafloor = (Herd, Herd, Grid<bool>);
+
+pub fn parse(contents: &str) -> AocResult<Seafloor> {
+  let cells = Grid::parse_chars(contents, "'>', 'v' or '.'", |c| {
+    matches!(c, '>' | 'v' | '.').then_some(c)
+  })?;
+  let herd = |kind| {
+    cells
+      .positions()
+      .filter(|&pos| cells[pos] == kind)
+      .collect()
+  };
+  Ok((herd('>'), herd('v'), cells.map(|&c| c != '.')))
+}
+
+/// Move every member of the herd one step in `dir` if the spot is free,
+/// returning how many moved.
+fn step(herd: &mut Herd, dir: (isize, isize), occupied: &mut Grid<bool>) -> usize {
+  let mut moves = vec![];
+  for pos in herd.iter_mut() {
+    let next = occupied.wrapping_offset(*pos, dir);
+    if !occupied[next] {
+      moves.push((*pos, next));
+      *pos = next;
     }
   }
-
-  Ok((east, south, occupied))
+  for &(from, to) in &moves {
+    assert!(occupied[from]);
+    assert!(!occupied[to]);
+    occupied[from] = false;
+    occupied[to] = true;
+  }
+  moves.len()
 }
 
-fn part1(fname: &str) -> AocResult<i32> {
-  let (mut east, mut south, mut occupied) = parse_input(fname)?;
-  let height = occupied.len();
-  let width = occupied[0].len();
+pub fn part1(seafloor: &Seafloor) -> AocResult<i32> {
+  let (mut east, mut south, mut occupied) = seafloor.clone();
 
   let mut iter = 0;
   loop {
     iter += 1;
-    let mut n_moves = 0;
-
-    let mut moves = vec![];
-    for (x, y) in &mut east {
-      let nx = (*x + 1)%width;
-      if !occupied[*y][nx] {
-        moves.push(((*x, *y), (nx, *y)));
-        *x = nx;
-      }
-    }
-    n_moves += moves.len();
-    for ((x1, y1), (x2, y2)) in moves {
-      assert_eq!(occupied[y1][x1], true);
-      assert_eq!(occupied[y2][x2], false);
-      occupied[y1][x1] = false;
-      occupied[y2][x2] = true;
+    let n_moves = step(&mut east, (1, 0), &mut occupied) + step(&mut south, (0, 1), &mut occupied);
+    if n_moves == 0 {
+      break;
     }
+  }
 
-    let mut moves = vec![];
-    for (x, y) in &mut south {
-      let ny = (*y + 1)%height;
-      if !occupied[(*y + 1)%height][*x] {
-        moves.push(((*x, *y), (*x, ny)));
-        *y = ny;
-      }
-    }
-    n_moves += moves.len();
-    for ((x1, y1), (x2, y2)) in moves {
-      assert_eq!(occupied[y1][x1], true);
-      assert_eq!(occupied[y2][x2], false);
-      occupied[y1][x1] = false;
-      occupied[y2][x2] = true;
-    }
+  Ok(iter)
+}
 
-    if n_moves == 0 { break; }
-  };
+// There is no second puzzle on the last day.
+pub fn part2(_seafloor: &Seafloor) -> AocResult<i32> { Ok(-1) }
 
-  Ok(iter)
+pub fn solve_str(input: &str) -> AocResult<(impl Display, impl Display)> {
+  let input = parse(input)?;
+  Ok((part1(&input)?, part2(&input)?))
 }
 
-pub fn run(fname: &str) -> AocResult<(impl Display, impl Display)> {
-  Ok((part1(fname)?, -1))
+pub struct Solver;
+
+impl Day for Solver {
+  const DAY: u32 = 25;
+  const TITLE: &'static str = "Sea Cucumber";
+  type Input = Seafloor;
+
+  fn parse(input: &str) -> AocResult<Self::Input> { parse(input) }
+  fn part1(input: &Self::Input) -> AocResult<impl Display> { part1(input) }
+  fn part2(input: &Self::Input) -> AocResult<impl Display> { part2(input) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>
+";
+
+  #[test]
+  fn sample() {
+    let input = parse(SAMPLE).unwrap();
+    assert_eq!(part1(&input).unwrap(), 58);
+  }
 }
@@ -1 +1,3 @@
-pub fn read_or_die(fname: &str) -> String { std::fs::read_to_string(fname).unwrap() }
+pub mod boxes;
+pub mod grid;
+pub mod search;
@@ -0,0 +1,234 @@
+/// A point in N dimensions.
+pub type Point<const N: usize> = [i64; N];
+
+/// An axis-aligned box in N dimensions, holding the points `p` with
+/// `min[d] <= p[d] < max[d]` on every axis `d`. Boxes are never empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AxisBox<const N: usize> {
+  min: Point<N>,
+  max: Point<N>,
+}
+
+impl<const N: usize> AxisBox<N> {
+  /// The box from `min` up to, but excluding, `max`, or `None` if that is
+  /// empty.
+  pub fn new(min: Point<N>, max: Point<N>) -> Option<Self> {
+    (0..N)
+      .all(|d| min[d] < max[d])
+      .then_some(AxisBox { min, max })
+  }
+
+  /// The box from `min` up to and including `max`, as puzzles usually give
+  /// ranges.
+  pub fn inclusive(min: Point<N>, max: Point<N>) -> Option<Self> {
+    Self::new(min, max.map(|v| v + 1))
+  }
+
+  pub fn min(&self) -> Point<N> { self.min }
+  pub fn max(&self) -> Point<N> { self.max }
+
+  /// The number of integer points in the box.
+  pub fn volume(&self) -> i64 { (0..N).map(|d| self.max[d] - self.min[d]).product() }
+
+  pub fn contains(&self, point: Point<N>) -> bool {
+    (0..N).all(|d| self.min[d] <= point[d] && point[d] < self.max[d])
+  }
+
+  pub fn intersect(&self, other: &Self) -> Option<Self> {
+    let min = std::array::from_fn(|d| self.min[d].max(other.min[d]));
+    let max = std::array::from_fn(|d| self.max[d].min(other.max[d]));
+    Self::new(min, max)
+  }
+
+  /// The part of the box outside of `other`, as at most `2 * N` disjoint
+  /// boxes.
+  pub fn difference(&self, other: &Self) -> Vec<Self> {
+    let Some(common) = self.intersect(other) else {
+      return vec![*self];
+    };
+    // Cut off the slabs below and above the common part one axis at a time,
+    // narrowing down what is left to the common part itself.
+    let mut pieces = vec![];
+    let mut rest = *self;
+    for d in 0..N {
+      if rest.min[d] < common.min[d] {
+        let mut below = rest;
+        below.max[d] = common.min[d];
+        pieces.push(below);
+        rest.min[d] = common.min[d];
+      }
+      if common.max[d] < rest.max[d] {
+        let mut above = rest;
+        above.min[d] = common.max[d];
+        pieces.push(above);
+        rest.max[d] = common.max[d];
+      }
+    }
+    pieces
+  }
+}
+
+/// A set of points made of disjoint axis-aligned boxes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoxSet<const N: usize> {
+  boxes: Vec<AxisBox<N>>,
+}
+
+impl<const N: usize> BoxSet<N> {
+  pub fn new() -> Self { BoxSet { boxes: vec![] } }
+
+  /// The disjoint boxes the set is made of.
+  pub fn boxes(&self) -> &[AxisBox<N>] { &self.boxes }
+
+  pub fn is_empty(&self) -> bool { self.boxes.is_empty() }
+
+  /// The number of integer points in the set.
+  pub fn volume(&self) -> i64 { self.boxes.iter().map(AxisBox::volume).sum() }
+
+  pub fn contains(&self, point: Point<N>) -> bool { self.boxes.iter().any(|b| b.contains(point)) }
+
+  /// Add all points of `b` to the set.
+  pub fn insert(&mut self, b: AxisBox<N>) {
+    self.remove(&b);
+    self.boxes.push(b);
+  }
+
+  /// Remove all points of `b` from the set.
+  pub fn remove(&mut self, b: &AxisBox<N>) {
+    let mut boxes = Vec::with_capacity(self.boxes.len());
+    for existing in &self.boxes {
+      if existing.intersect(b).is_some() {
+        boxes.extend(existing.difference(b));
+      } else {
+        boxes.push(*existing);
+      }
+    }
+    self.boxes = boxes;
+  }
+
+  pub fn union(&self, other: &Self) -> Self {
+    let mut result = self.clone();
+    for &b in &other.boxes {
+      result.insert(b);
+    }
+    result
+  }
+
+  pub fn intersection(&self, other: &Self) -> Self {
+    // Intersections of disjoint boxes with disjoint boxes are disjoint.
+    let boxes = self
+      .boxes
+      .iter()
+      .flat_map(|a| other.boxes.iter().filter_map(|b| a.intersect(b)))
+      .collect();
+    BoxSet { boxes }
+  }
+
+  pub fn difference(&self, other: &Self) -> Self {
+    let mut result = self.clone();
+    for b in &other.boxes {
+      result.remove(b);
+    }
+    result
+  }
+}
+
+impl<const N: usize> From<AxisBox<N>> for BoxSet<N> {
+  fn from(b: AxisBox<N>) -> Self { BoxSet { boxes: vec![b] } }
+}
+
+/// The union of all the boxes.
+impl<const N: usize> FromIterator<AxisBox<N>> for BoxSet<N> {
+  fn from_iter<I: IntoIterator<Item = AxisBox<N>>>(iter: I) -> Self {
+    let mut set = BoxSet::new();
+    for b in iter {
+      set.insert(b);
+    }
+    set
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use itertools::Itertools;
+
+  use super::*;
+
+  fn square(x: i64, y: i64, size: i64) -> AxisBox<2> {
+    AxisBox::new([x, y], [x + size, y + size]).unwrap()
+  }
+
+  #[test]
+  fn single_boxes() {
+    assert_eq!(AxisBox::new([0, 0, 0], [1, 0, 1]), None);
+    let cube = AxisBox::inclusive([10, 10, 10], [12, 12, 12]).unwrap();
+    assert_eq!(cube.volume(), 27);
+    assert!(cube.contains([12, 10, 11]));
+    assert!(!cube.contains([13, 10, 11]));
+
+    let (a, b) = (square(0, 0, 4), square(2, 2, 4));
+    assert_eq!(a.intersect(&b), Some(square(2, 2, 2)));
+    assert_eq!(a.intersect(&square(4, 0, 4)), None);
+    let pieces = a.difference(&b);
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(pieces.iter().map(AxisBox::volume).sum::<i64>(), 12);
+    assert_eq!(a.difference(&square(1, 1, 2)).len(), 4);
+    assert_eq!(a.difference(&square(-1, -1, 6)), vec![]);
+    assert_eq!(a.difference(&square(5, 5, 1)), vec![a]);
+  }
+
+  #[test]
+  fn set_operations() {
+    let a = BoxSet::from(square(0, 0, 4));
+    let b = BoxSet::from(square(2, 2, 4));
+    assert_eq!(a.union(&b).volume(), 28);
+    assert_eq!(a.intersection(&b).volume(), 4);
+    assert_eq!(a.difference(&b).volume(), 12);
+    assert!(a.difference(&a).is_empty());
+  }
+
+  #[test]
+  fn matches_points() {
+    let mut seed = 22u64;
+    let mut rand = |n: i64| {
+      seed ^= seed << 13;
+      seed ^= seed >> 7;
+      seed ^= seed << 17;
+      (seed % n as u64) as i64
+    };
+    let mut boxes = || {
+      (0..6)
+        .filter_map(|_| {
+          let min = [rand(8), rand(8), rand(8)];
+          AxisBox::new(min, min.map(|v| v + 1 + rand(5)))
+        })
+        .collect_vec()
+    };
+    let (a, b) = (boxes(), boxes());
+    let in_any = |boxes: &[AxisBox<3>], p| boxes.iter().any(|b| b.contains(p));
+    let (sa, sb) = (
+      a.iter().copied().collect::<BoxSet<3>>(),
+      BoxSet::from_iter(b.clone()),
+    );
+    type Op = fn(bool, bool) -> bool;
+    let sets: [(BoxSet<3>, Op); 3] = [
+      (sa.union(&sb), |x, y| x || y),
+      (sa.intersection(&sb), |x, y| x && y),
+      (sa.difference(&sb), |x, y| x && !y),
+    ];
+    for (set, op) in sets {
+      let mut count = 0;
+      for p in (0..3).map(|_| 0..13).multi_cartesian_product() {
+        let p = [p[0], p[1], p[2]];
+        let expected = op(in_any(&a, p), in_any(&b, p));
+        assert_eq!(set.contains(p), expected, "{:?}", p);
+        count += expected as i64;
+      }
+      assert_eq!(set.volume(), count);
+      // The decomposition is disjoint.
+      for [x, y] in set.boxes().iter().array_combinations() {
+        assert_eq!(x.intersect(y), None);
+      }
+    }
+  }
+}
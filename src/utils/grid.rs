@@ -0,0 +1,293 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::error::{input_error, AocResult};
+
+/// A position in a grid, as `(x, y)` with `y` growing downwards.
+pub type Pos = (usize, usize);
+
+const DIRS4: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const DIRS8: [(isize, isize); 8] = [
+  (1, 0),
+  (1, 1),
+  (0, 1),
+  (-1, 1),
+  (-1, 0),
+  (-1, -1),
+  (0, -1),
+  (1, -1),
+];
+
+/// A rectangular grid stored row by row in a single vector.
+///
+/// Indexing with `grid[(x, y)]` panics outside the grid, `get` returns `None`
+/// instead.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+  width: usize,
+  height: usize,
+  cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+  pub fn new(width: usize, height: usize, value: T) -> Self
+  where
+    T: Clone,
+  {
+    Grid { width, height, cells: vec![value; width * height] }
+  }
+
+  /// A grid with the value at every position given by `f`.
+  pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+    let cells = (0..height)
+      .flat_map(|y| (0..width).map(move |x| (x, y)))
+      .map(&mut f)
+      .collect();
+    Grid { width, height, cells }
+  }
+
+  /// A grid from its rows, which must all have the same length.
+  pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+    let height = rows.len();
+    let width = rows.first().map_or(0, |row| row.len());
+    assert!(
+      rows.iter().all(|row| row.len() == width),
+      "rows differ in length"
+    );
+    Grid { width, height, cells: rows.into_iter().flatten().collect() }
+  }
+
+  pub fn width(&self) -> usize { self.width }
+  pub fn height(&self) -> usize { self.height }
+
+  pub fn contains(&self, (x, y): Pos) -> bool { x < self.width && y < self.height }
+
+  pub fn get(&self, pos: Pos) -> Option<&T> {
+    if self.contains(pos) {
+      Some(&self.cells[pos.1 * self.width + pos.0])
+    } else {
+      None
+    }
+  }
+
+  pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+    if self.contains(pos) {
+      Some(&mut self.cells[pos.1 * self.width + pos.0])
+    } else {
+      None
+    }
+  }
+
+  /// Move `d` away from `pos`, if that stays inside the grid.
+  pub fn offset(&self, (x, y): Pos, (dx, dy): (isize, isize)) -> Option<Pos> {
+    let pos = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+    self.contains(pos).then_some(pos)
+  }
+
+  /// Move `d` away from `pos`, wrapping around the edges as on a torus.
+  pub fn wrapping_offset(&self, (x, y): Pos, (dx, dy): (isize, isize)) -> Pos {
+    let wrap = |v: usize, d: isize, n: usize| (v as isize + d).rem_euclid(n as isize) as usize;
+    (wrap(x, dx, self.width), wrap(y, dy, self.height))
+  }
+
+  /// The horizontal and vertical neighbours of `pos` inside the grid.
+  pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+    DIRS4.into_iter().filter_map(move |d| self.offset(pos, d))
+  }
+
+  /// The neighbours of `pos` inside the grid, including diagonal ones.
+  pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+    DIRS8.into_iter().filter_map(move |d| self.offset(pos, d))
+  }
+
+  /// The horizontal and vertical neighbours of `pos`, wrapping around the edges.
+  pub fn wrapping_neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+    DIRS4.into_iter().map(move |d| self.wrapping_offset(pos, d))
+  }
+
+  /// All neighbours of `pos`, including diagonal ones, wrapping around the edges.
+  pub fn wrapping_neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+    DIRS8.into_iter().map(move |d| self.wrapping_offset(pos, d))
+  }
+
+  /// All positions, row by row.
+  pub fn positions(&self) -> impl Iterator<Item = Pos> {
+    let width = self.width;
+    (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+  }
+
+  /// All values, row by row.
+  pub fn iter(&self) -> std::slice::Iter<'_, T> { self.cells.iter() }
+
+  pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> { self.cells.iter_mut() }
+
+  pub fn row(&self, y: usize) -> &[T] { &self.cells[y * self.width..(y + 1) * self.width] }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+    // chunks panics on 0, and an empty grid has no rows anyway.
+    self.cells.chunks(self.width.max(1))
+  }
+
+  pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+    Grid {
+      width: self.width,
+      height: self.height,
+      cells: self.cells.iter().map(f).collect(),
+    }
+  }
+
+  /// Parse a map with one cell per character, converted by `cell`. Characters
+  /// it rejects are reported as not being `expected`.
+  pub fn parse_chars(
+    input: &str,
+    expected: &str,
+    cell: impl FnMut(char) -> Option<T>,
+  ) -> AocResult<Self> {
+    Grid::parse_chars_in(input, input, expected, cell)
+  }
+
+  /// `parse_chars` for a `section` of a larger `input`, so that errors point
+  /// at the right line of `input`.
+  pub fn parse_chars_in(
+    input: &str,
+    section: &str,
+    expected: &str,
+    mut cell: impl FnMut(char) -> Option<T>,
+  ) -> AocResult<Self> {
+    let mut cells = vec![];
+    let mut width = None;
+    let mut height = 0;
+    for line in section.lines() {
+      let before = cells.len();
+      for (i, c) in line.char_indices() {
+        match cell(c) {
+          Some(value) => cells.push(value),
+          None => {
+            let at = &line[i..i + c.len_utf8()];
+            return Err(input_error(input, at, format!("Expected {}", expected)));
+          }
+        }
+      }
+      let len = cells.len() - before;
+      match width {
+        None => width = Some(len),
+        Some(width) if width != len => {
+          return Err(input_error(
+            input,
+            line,
+            format!("Expected a row of {} cells", width),
+          ));
+        }
+        _ => {}
+      }
+      height += 1;
+    }
+    match width {
+      Some(width) if width > 0 => Ok(Grid { width, height, cells }),
+      _ => Err(input_error(input, section, "Empty grid")),
+    }
+  }
+}
+
+impl Grid<u8> {
+  /// Parse a map of single digits like `2199943210`.
+  pub fn parse_digits(input: &str) -> AocResult<Self> {
+    Grid::parse_chars(input, "a digit", |c| c.to_digit(10).map(|d| d as u8))
+  }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+  type Output = T;
+  fn index(&self, (x, y): Pos) -> &T {
+    assert!(
+      x < self.width,
+      "x = {} outside grid of width {}",
+      x,
+      self.width
+    );
+    &self.cells[y * self.width + x]
+  }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+  fn index_mut(&mut self, (x, y): Pos) -> &mut T {
+    assert!(
+      x < self.width,
+      "x = {} outside grid of width {}",
+      x,
+      self.width
+    );
+    &mut self.cells[y * self.width + x]
+  }
+}
+
+/// One line per row, with the cells next to each other.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (y, row) in self.rows().enumerate() {
+      if y > 0 {
+        writeln!(f)?;
+      }
+      for value in row {
+        write!(f, "{}", value)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DIGITS: &str = "123\n456\n";
+
+  #[test]
+  fn parse_and_display() {
+    let grid = Grid::parse_digits(DIGITS).unwrap();
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid[(2, 0)], 3);
+    assert_eq!(grid[(0, 1)], 4);
+    assert_eq!(grid.get((3, 0)), None);
+    assert_eq!(grid.get((0, 2)), None);
+    assert_eq!(grid.to_string(), DIGITS.trim_end());
+  }
+
+  #[test]
+  fn parse_errors() {
+    let err = Grid::parse_digits("12\n3x\n").unwrap_err().to_string();
+    assert!(
+      err.starts_with("line 2, column 2: Expected a digit 'x'"),
+      "{}",
+      err
+    );
+    let err = Grid::parse_digits("12\n345\n").unwrap_err().to_string();
+    assert!(
+      err.starts_with("line 2, column 1: Expected a row of 2 cells"),
+      "{}",
+      err
+    );
+    assert!(Grid::parse_digits("").is_err());
+  }
+
+  #[test]
+  fn neighbours() {
+    let grid = Grid::new(3, 3, 0);
+    let sorted = |it: &mut dyn Iterator<Item = Pos>| {
+      let mut v = it.collect::<Vec<_>>();
+      v.sort();
+      v
+    };
+    assert_eq!(sorted(&mut grid.neighbours4((0, 0))), [(0, 1), (1, 0)]);
+    assert_eq!(
+      sorted(&mut grid.neighbours8((0, 0))),
+      [(0, 1), (1, 0), (1, 1)]
+    );
+    assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+    assert_eq!(
+      sorted(&mut grid.wrapping_neighbours4((0, 0))),
+      [(0, 1), (0, 2), (1, 0), (2, 0)]
+    );
+    assert_eq!(grid.wrapping_offset((2, 2), (1, 1)), (0, 0));
+  }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::hash::Hash;
+
+use hashbrown::HashMap;
+
+pub type Cost = u64;
+
+/// A graph to find a cheapest path through, from some start state to any
+/// goal state.
+pub trait SearchProblem {
+  /// States that compare equal are treated as the same node.
+  type State: Clone + Eq + Hash;
+
+  /// The states reachable in one step from `state`, with the cost of the step.
+  fn successors(&self, state: &Self::State) -> impl Iterator<Item = (Self::State, Cost)>;
+  fn is_goal(&self, state: &Self::State) -> bool;
+}
+
+/// A cheapest path found by a search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<S> {
+  pub cost: Cost,
+  /// All states from the start to the goal, both included.
+  pub states: Vec<S>,
+}
+
+/// The states seen so far, with the cheapest known way to reach them.
+struct Visited<S> {
+  index: HashMap<S, usize>,
+  states: Vec<S>,
+  cost: Vec<Cost>,
+  parent: Vec<Option<usize>>,
+}
+
+impl<S: Clone + Eq + Hash> Visited<S> {
+  fn new(start: S) -> Self {
+    Visited {
+      index: HashMap::from([(start.clone(), 0)]),
+      states: vec![start],
+      cost: vec![0],
+      parent: vec![None],
+    }
+  }
+
+  /// Record reaching `state` from the state at `from` with total `cost`.
+  /// Returns its index if that is cheaper than any way seen before.
+  fn relax(&mut self, state: S, cost: Cost, from: usize) -> Option<usize> {
+    match self.index.get(&state) {
+      Some(&i) if self.cost[i] <= cost => None,
+      Some(&i) => {
+        self.cost[i] = cost;
+        self.parent[i] = Some(from);
+        Some(i)
+      }
+      None => {
+        let i = self.states.len();
+        self.index.insert(state.clone(), i);
+        self.states.push(state);
+        self.cost.push(cost);
+        self.parent.push(Some(from));
+        Some(i)
+      }
+    }
+  }
+
+  fn path_to(&self, goal: usize) -> Path<S> {
+    let mut states = vec![self.states[goal].clone()];
+    let mut cur = goal;
+    while let Some(parent) = self.parent[cur] {
+      states.push(self.states[parent].clone());
+      cur = parent;
+    }
+    states.reverse();
+    Path { cost: self.cost[goal], states }
+  }
+}
+
+/// Cheapest path from `start` to a goal, using Dijkstra's algorithm.
+pub fn dijkstra<P: SearchProblem>(problem: &P, start: P::State) -> Option<Path<P::State>> {
+  astar(problem, start, |_| 0)
+}
+
+/// Cheapest path from `start` to a goal, using A*. The `heuristic` estimates
+/// the remaining cost to a goal; the path is optimal if it never overestimates.
+pub fn astar<P: SearchProblem>(
+  problem: &P,
+  start: P::State,
+  heuristic: impl Fn(&P::State) -> Cost,
+) -> Option<Path<P::State>> {
+  let mut queue = BinaryHeap::from([Reverse((heuristic(&start), 0, 0))]);
+  let mut visited = Visited::new(start);
+  // Reused between iterations, as successors borrow the visited state.
+  let mut next_states = vec![];
+
+  while let Some(Reverse((_, cost, i))) = queue.pop() {
+    if cost > visited.cost[i] {
+      // A cheaper way to this state was found after this one was queued.
+      continue;
+    }
+    if problem.is_goal(&visited.states[i]) {
+      return Some(visited.path_to(i));
+    }
+    next_states.extend(problem.successors(&visited.states[i]));
+    for (next, step) in next_states.drain(..) {
+      let estimate = cost + step + heuristic(&next);
+      if let Some(j) = visited.relax(next, cost + step, i) {
+        queue.push(Reverse((estimate, cost + step, j)));
+      }
+    }
+  }
+  None
+}
+
+/// Cheapest path from `start` to a goal, using Dijkstra's algorithm with
+/// Dial's bucket queue. Only works for integer step costs of at most
+/// `max_step`, but avoids the heap's logarithmic cost for small ones.
+pub fn dial<P: SearchProblem>(
+  problem: &P,
+  start: P::State,
+  max_step: Cost,
+) -> Option<Path<P::State>> {
+  // Costs of queued states span at most max_step, so the buckets can be reused
+  // cyclically.
+  let n = max_step as usize + 1;
+  let mut buckets = vec![vec![]; n];
+  buckets[0].push(0);
+  let mut queued = 1;
+  let mut visited = Visited::new(start);
+  let mut next_states = vec![];
+
+  let mut cost = 0;
+  while queued > 0 {
+    let Some(i) = buckets[cost as usize % n].pop() else {
+      cost += 1;
+      continue;
+    };
+    queued -= 1;
+    if cost > visited.cost[i] {
+      continue;
+    }
+    if problem.is_goal(&visited.states[i]) {
+      return Some(visited.path_to(i));
+    }
+    next_states.extend(problem.successors(&visited.states[i]));
+    for (next, step) in next_states.drain(..) {
+      assert!(
+        step <= max_step,
+        "step cost {} above the maximum {}",
+        step,
+        max_step
+      );
+      if let Some(j) = visited.relax(next, cost + step, i) {
+        buckets[(cost + step) as usize % n].push(j);
+        queued += 1;
+      }
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Walk on 0..=20, with steps of +1 (cost 4), +3 (cost 5) and -1
+  /// (cost 1).
+  struct Line {
+    goal: i32,
+  }
+
+  impl SearchProblem for Line {
+    type State = i32;
+    fn successors(&self, &x: &i32) -> impl Iterator<Item = (i32, Cost)> {
+      [(x + 1, 4), (x + 3, 5), (x - 1, 1)]
+        .into_iter()
+        .filter(|&(x, _)| (0..=20).contains(&x))
+    }
+    fn is_goal(&self, &x: &i32) -> bool { x == self.goal }
+  }
+
+  /// Check that `path` is a valid route from 0 to the goal with total cost 11,
+  /// the cheapest (e.g. 0 -> 3 -> 6 -> 5).
+  fn check(line: &Line, path: Option<Path<i32>>) {
+    let path = path.unwrap();
+    assert_eq!(path.cost, 11);
+    assert_eq!(path.states.first(), Some(&0));
+    assert_eq!(path.states.last(), Some(&line.goal));
+    let steps = path.states.windows(2).map(|w| {
+      line
+        .successors(&w[0])
+        .find(|&(x, _)| x == w[1])
+        .map(|(_, cost)| cost)
+        .unwrap()
+    });
+    assert_eq!(steps.sum::<Cost>(), path.cost);
+  }
+
+  #[test]
+  fn all_agree() {
+    let line = Line { goal: 5 };
+    check(&line, dijkstra(&line, 0));
+    check(
+      &line,
+      astar(&line, 0, |&x| (x - 5).unsigned_abs() as Cost / 3),
+    );
+    check(&line, dial(&line, 0, 5));
+  }
+
+  #[test]
+  fn start_is_goal_and_unreachable() {
+    let line = Line { goal: 0 };
+    assert_eq!(dijkstra(&line, 0), Some(Path { cost: 0, states: vec![0] }));
+    let line = Line { goal: 100 };
+    assert_eq!(dijkstra(&line, 0), None);
+    assert_eq!(dial(&line, 0, 5), None);
+  }
+}